@@ -6,18 +6,26 @@ use std::env::args;
 use std::io::{stdin, BufRead};
 use std::str::FromStr;
 
-use reservoir::sample;
+use reservoir::{sample, seeded_sample};
 
+// Usage: random-sample [COUNT [SEED]]
+//
+// Prints a random sample of COUNT lines (default 10) of the standard
+// input.  If a SEED is given, prints the same sample every time for
+// the same input.
 fn main() {
     let count = args().nth(1).and_then(|s| FromStr::from_str(s.as_ref()).ok()).unwrap_or(10);
+    let seed : Option<u64> = args().nth(2).and_then(|s| FromStr::from_str(s.as_ref()).ok());
 
     let input = stdin();
     let input_lines = input.lock().lines().map(|r| r.unwrap());
     
-    let samples = sample(&mut rand::thread_rng(), count, input_lines);
+    let samples = match seed {
+        Some(seed) => seeded_sample(seed, count, input_lines),
+        None => sample(&mut rand::thread_rng(), count, input_lines)
+    };
     
     for sample in samples.iter() {
         println!("{}", sample);
     }
 }
-
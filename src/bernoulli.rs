@@ -0,0 +1,128 @@
+use rand::Rng;
+
+use super::open01;
+
+/// An iterator adapter that yields each item of the underlying
+/// iterator independently with a fixed probability.
+///
+/// Rather than drawing a random number for every item, calculates the
+/// geometrically distributed number of items to skip before the next
+/// item it yields, and skips over them with `Iterator::nth`.
+///
+/// The number of items yielded varies.  Multiplying a count or sum
+/// over the yielded items by the `scale_factor`, 1/p, gives an
+/// unbiased estimate of the count or sum over all the items.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::BernoulliSample;
+/// # fn main() {
+/// let sample = BernoulliSample::new(0..1000000, 0.01, thread_rng());
+/// let scale_factor = sample.scale_factor();
+///
+/// let estimated_count = sample.count() as f64 * scale_factor;
+///
+/// assert_eq!(100.0, scale_factor);
+/// assert!(estimated_count > 950000.0 && estimated_count < 1050000.0);
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the probability is not between 0 and 1.
+#[derive(Clone, Debug)]
+pub struct BernoulliSample<I, RNG> {
+    iter : I,
+    probability : f64,
+    log_complement : f64,
+    rng : RNG
+}
+
+impl<I, RNG> BernoulliSample<I, RNG>
+    where I : Iterator,
+          RNG : Rng
+{
+    /// Sample the items of `iter`, each with the given `probability`,
+    /// with random numbers from `rng`.
+    pub fn new(iter : I, probability : f64, rng : RNG) -> Self {
+        assert!((0.0..=1.0).contains(&probability), "probability {} is not between 0 and 1", probability);
+        
+        BernoulliSample {
+            iter,
+            probability,
+            log_complement: (-probability).ln_1p(),
+            rng
+        }
+    }
+    
+    /// The probability with which each item is sampled.
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+    
+    /// The reciprocal of the probability, by which to scale counts and
+    /// sums over the sample to estimate them over all the items.
+    pub fn scale_factor(&self) -> f64 {
+        1.0 / self.probability
+    }
+}
+
+impl<I, RNG> Iterator for BernoulliSample<I, RNG>
+    where I : Iterator,
+          RNG : Rng
+{
+    type Item = I::Item;
+    
+    fn next(&mut self) -> Option<I::Item> {
+        if self.probability == 0.0 {
+            return None;
+        }
+        
+        let skip = (open01(&mut self.rng).ln() / self.log_complement).floor();
+        if skip >= usize::MAX as f64 {
+            return None;
+        }
+        self.iter.nth(skip as usize)
+    }
+    
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// Return a sample of an iterator that includes each item independently
+/// with the given `probability`, in the order in which they occur.
+///
+/// See `BernoulliSample`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::bernoulli_sample;
+/// # fn main() {
+/// let samples = bernoulli_sample(&mut thread_rng(), 0.1, 0..10000);
+///
+/// assert!(samples.len() > 800 && samples.len() < 1200);
+/// assert!(samples.windows(2).all(|w| w[0] < w[1]));
+///
+/// assert_eq!(10000, bernoulli_sample(&mut thread_rng(), 1.0, 0..10000).len());
+/// assert!(bernoulli_sample(&mut thread_rng(), 0.0, 0..10000).is_empty());
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the probability is not between 0 and 1.
+pub fn bernoulli_sample<I, RNG>(rng : &mut RNG, probability : f64, iter : I) -> Vec<I::Item>
+    where I : Iterator,
+          RNG : Rng
+{
+    BernoulliSample::new(iter, probability, rng).collect()
+}
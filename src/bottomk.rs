@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use super::hash::{SeededHash, StableHash};
+
+/// A consistent sample of a stream: the items whose keys have the
+/// smallest hashes.
+///
+/// Keys are hashed with SipHash-2-4, keyed by a seed, rather than
+/// being ranked by random numbers, so sketches with the same seed
+/// choose the same keys on every machine and in every run, without
+/// coordination.  Items with the same key are the same item to the
+/// sketch, which keeps the first one it is given.
+///
+/// Keys are hashed by their `StableHash` encoding rather than by their
+/// implementations of `Hash`, whose output the standard library does
+/// not guarantee, so hashes are the same on every platform and with
+/// every version of Rust.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::BottomK;
+/// # fn main() {
+/// let mut here = BottomK::new(3, 42);
+/// let mut there = BottomK::new(3, 42);
+///
+/// for user_id in 0..1000 {
+///     here.insert(user_id);
+/// }
+/// for user_id in (0..1000).rev() {
+///     there.insert(user_id);
+/// }
+///
+/// let samples : Vec<&i32> = here.samples().collect();
+/// assert_eq!(3, samples.len());
+/// assert_eq!(samples, there.samples().collect::<Vec<&i32>>());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BottomK<T> {
+    sample_size : usize,
+    hash : SeededHash,
+    items : BTreeMap<u64, T>
+}
+
+impl<T> BottomK<T> {
+    /// Create an empty sketch that will hold the `sample_size` items
+    /// with the smallest hashes, hashing keys with the `seed`.
+    pub fn new(sample_size : usize, seed : u64) -> Self {
+        BottomK {
+            sample_size,
+            hash: SeededHash::new(seed),
+            items: BTreeMap::new()
+        }
+    }
+    
+    /// Offer an item, which is its own key, to the sketch.
+    pub fn insert(&mut self, item : T)
+        where T : StableHash
+    {
+        let hash = self.hash.stable_hash(&item);
+        self.insert_hashed(hash, item);
+    }
+    
+    /// Offer an item to the sketch, ranking it by the hash of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::BottomK;
+    /// # fn main() {
+    /// let mut sketch = BottomK::new(2, 7);
+    ///
+    /// sketch.insert_keyed(&"alice", ("alice", "login"));
+    /// sketch.insert_keyed(&"bob", ("bob", "login"));
+    /// sketch.insert_keyed(&"alice", ("alice", "logout"));
+    ///
+    /// assert_eq!(2, sketch.len());
+    /// assert!(sketch.samples().any(|e| *e == ("alice", "login")));
+    /// # }
+    /// ```
+    pub fn insert_keyed<K>(&mut self, key : &K, item : T)
+        where K : StableHash + ?Sized
+    {
+        let hash = self.hash.stable_hash(key);
+        self.insert_hashed(hash, item);
+    }
+    
+    /// The hash by which the sketch ranks `key`.
+    ///
+    /// # Examples
+    ///
+    /// Hashes depend only on the seed and the key:
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::BottomK;
+    /// # fn main() {
+    /// let sketch = BottomK::<String>::new(10, 42);
+    ///
+    /// assert_eq!(15172225925711767013, sketch.hash_of("user-1234"));
+    /// assert_eq!(6600484232330243234, sketch.hash_of(&1234u32));
+    /// assert_eq!(9739433309110974574, sketch.hash_of(&("user", 1234u32)));
+    /// # }
+    /// ```
+    pub fn hash_of<K>(&self, key : &K) -> u64
+        where K : StableHash + ?Sized
+    {
+        self.hash.stable_hash(key)
+    }
+    
+    /// The seed with which the sketch hashes keys.
+    pub fn seed(&self) -> u64 {
+        self.hash.seed()
+    }
+    
+    /// The maximum number of items the sketch will hold.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+    
+    /// The number of items held by the sketch.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    
+    /// Whether the sketch holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    
+    /// The items held by the sketch, in ascending order of hash.
+    pub fn samples(&self) -> impl Iterator<Item=&T> {
+        self.items.values()
+    }
+    
+    /// The hashes of the items held by the sketch, in ascending order.
+    pub fn hashes(&self) -> impl Iterator<Item=u64> + '_ {
+        self.items.keys().cloned()
+    }
+    
+    /// Consume the sketch, returning the items it holds in ascending
+    /// order of hash.
+    pub fn into_samples(self) -> Vec<T> {
+        self.items.into_values().collect()
+    }
+    
+    /// Merge two sketches into a sketch of the union of their streams.
+    /// The sample size of the merged sketch is the smaller of the two
+    /// sketches' sample sizes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches hash keys with different seeds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::BottomK;
+    /// # fn main() {
+    /// let mut left = BottomK::new(5, 42);
+    /// let mut right = BottomK::new(5, 42);
+    /// let mut whole = BottomK::new(5, 42);
+    ///
+    /// for i in 0..100 {
+    ///     left.insert(i);
+    ///     whole.insert(i);
+    /// }
+    /// for i in 50..200 {
+    ///     right.insert(i);
+    ///     whole.insert(i);
+    /// }
+    ///
+    /// assert_eq!(whole.into_samples(), left.merge(right).into_samples());
+    /// # }
+    /// ```
+    pub fn merge(mut self, other : BottomK<T>) -> BottomK<T> {
+        assert_eq!(self.hash, other.hash, "cannot merge sketches with different seeds");
+        
+        self.sample_size = self.sample_size.min(other.sample_size);
+        for (hash, item) in other.items {
+            self.insert_hashed(hash, item);
+        }
+        self.truncate();
+        self
+    }
+    
+    /// Estimate the Jaccard similarity of the sets of keys of the
+    /// streams summarised by two sketches: the number of keys in both
+    /// streams divided by the number of keys in either.
+    ///
+    /// The estimate is the fraction of the smallest hashes of the union
+    /// of the two sketches that occur in both sketches.  Its standard
+    /// error is about 1/sqrt(k), where k is the smaller of the sample
+    /// sizes.  Returns `None` if both sketches are empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches hash keys with different seeds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::BottomK;
+    /// # fn main() {
+    /// let mut a = BottomK::new(1000, 42);
+    /// let mut b = BottomK::new(1000, 42);
+    ///
+    /// a.extend(0..20000);
+    /// b.extend(10000..30000);
+    ///
+    /// // The true similarity is 10000/30000
+    /// let similarity = a.jaccard(&b).unwrap();
+    /// assert!(similarity > 0.28 && similarity < 0.39);
+    /// # }
+    /// ```
+    pub fn jaccard(&self, other : &BottomK<T>) -> Option<f64> {
+        assert_eq!(self.hash, other.hash, "cannot compare sketches with different seeds");
+        
+        let k = self.sample_size.min(other.sample_size);
+        let mut union : Vec<u64> = self.items.keys().chain(other.items.keys()).cloned().collect();
+        union.sort();
+        union.dedup();
+        union.truncate(k);
+        
+        if union.is_empty() {
+            return None;
+        }
+        
+        let both = union.iter()
+            .filter(|h| self.items.contains_key(h) && other.items.contains_key(h))
+            .count();
+        Some(both as f64 / union.len() as f64)
+    }
+    
+    fn insert_hashed(&mut self, hash : u64, item : T) {
+        if self.items.contains_key(&hash) {
+            return;
+        }
+        
+        let full = self.items.len() >= self.sample_size;
+        if full && self.items.keys().next_back().is_none_or(|&max| hash >= max) {
+            return;
+        }
+        
+        self.items.insert(hash, item);
+        self.truncate();
+    }
+    
+    fn truncate(&mut self) {
+        while self.items.len() > self.sample_size {
+            let max = *self.items.keys().next_back().unwrap();
+            self.items.remove(&max);
+        }
+    }
+}
+
+impl<T : StableHash> Extend<T> for BottomK<T> {
+    fn extend<I : IntoIterator<Item=T>>(&mut self, iter : I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
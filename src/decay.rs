@@ -0,0 +1,241 @@
+use rand::{Rng, ThreadRng};
+
+use super::open01;
+use super::weighted::KeyHeap;
+
+// Rescale the weights when the exponent of the newest item's weight
+// exceeds this, long before weights and priorities could overflow.
+const RESCALE_EXPONENT : f64 = 50.0;
+
+/// A reservoir of numeric values that is biased towards recently
+/// pushed values, for recording metrics such as latencies.
+///
+/// Implements the forward decay scheme of Cormode, Shkapenyuk,
+/// Srivastava and Xu, as used by Dropwizard Metrics'
+/// ExponentiallyDecayingReservoir.  A value pushed at time t is given
+/// the weight e^(alpha(t-L)), relative to a landmark time L, and the
+/// reservoir holds a weighted sample of the values.  The larger
+/// `alpha`, the more the sample is biased towards recent values.
+/// Dropwizard uses an alpha of 0.015 with times in seconds, which
+/// represents roughly the last five minutes of data.
+///
+/// The weights of new values grow exponentially, so the reservoir
+/// periodically moves the landmark forward to the current time and
+/// rescales the weights of the values it holds, to keep them from
+/// overflowing.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::DecayingReservoir;
+/// # fn main() {
+/// let mut reservoir = DecayingReservoir::new(100, 0.015, thread_rng());
+///
+/// for t in 0..10000 {
+///     let latency = if t < 9000 { 10.0 } else { 500.0 };
+///     reservoir.push(t as f64, latency);
+/// }
+///
+/// let snapshot = reservoir.snapshot();
+///
+/// assert_eq!(100, snapshot.len());
+/// assert_eq!(Some(500.0), snapshot.median());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DecayingReservoir<RNG = ThreadRng> {
+    alpha : f64,
+    landmark : Option<f64>,
+    samples : KeyHeap<(f64, f64)>,
+    rng : RNG
+}
+
+impl<RNG> DecayingReservoir<RNG>
+    where RNG : Rng
+{
+    /// Create an empty reservoir that will hold at most `sample_size`
+    /// values, decaying at the rate `alpha` per unit of time, chosen
+    /// with random numbers from `rng`.
+    pub fn new(sample_size : usize, alpha : f64, rng : RNG) -> Self {
+        DecayingReservoir {
+            alpha,
+            landmark: None,
+            samples: KeyHeap::new(sample_size),
+            rng
+        }
+    }
+    
+    /// Push a value that was recorded at `time`, taking the time first,
+    /// as `TimeWindowReservoir::push` does.
+    ///
+    /// Times are in whatever units `alpha` was chosen for, and are
+    /// expected to increase, or at least not to decrease by much.
+    pub fn push(&mut self, time : f64, value : f64) {
+        let landmark = match self.landmark {
+            Some(landmark) if self.alpha * (time - landmark) > RESCALE_EXPONENT => {
+                self.rescale(landmark, time);
+                time
+            }
+            Some(landmark) => landmark,
+            None => {
+                self.landmark = Some(time);
+                time
+            }
+        };
+        
+        let weight = (self.alpha * (time - landmark)).exp();
+        let priority = weight / open01(&mut self.rng);
+        self.samples.offer(priority, (value, weight));
+    }
+    
+    /// The number of values held by the reservoir.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    
+    /// Whether the reservoir holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.samples.len() == 0
+    }
+    
+    /// Statistics of the values held by the reservoir, weighted by
+    /// their decayed weights.
+    pub fn snapshot(&self) -> WeightedSnapshot {
+        WeightedSnapshot::new(self.samples.items().cloned())
+    }
+    
+    fn rescale(&mut self, old_landmark : f64, new_landmark : f64) {
+        let factor = (-self.alpha * (new_landmark - old_landmark)).exp();
+        
+        if factor > 0.0 {
+            self.samples.rescale(factor, |&mut (_, ref mut weight)| *weight *= factor);
+        } else {
+            self.samples.clear();
+        }
+        self.landmark = Some(new_landmark);
+    }
+}
+
+/// Statistics of a set of weighted values.
+///
+/// All the statistics are `None` if there are no values.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::WeightedSnapshot;
+/// # fn main() {
+/// let snapshot = WeightedSnapshot::new(vec![(1.0, 1.0), (2.0, 1.0), (3.0, 2.0)]);
+///
+/// assert_eq!(Some(1.0), snapshot.min());
+/// assert_eq!(Some(3.0), snapshot.max());
+/// assert_eq!(Some(2.25), snapshot.mean());
+/// assert_eq!(Some(2.0), snapshot.quantile(0.4));
+/// assert_eq!(Some(3.0), snapshot.median());
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedSnapshot {
+    values : Vec<f64>,
+    weights : Vec<f64>,
+    cumulative_weights : Vec<f64>
+}
+
+impl WeightedSnapshot {
+    /// Create a snapshot of `(value, weight)` pairs.  The weights are
+    /// normalised to sum to one.  The values are ordered by
+    /// `f64::total_cmp`, so any NaN values sort above infinity, or
+    /// below negative infinity if their sign bit is set.
+    pub fn new<I>(weighted_values : I) -> Self
+        where I : IntoIterator<Item=(f64, f64)>
+    {
+        let mut pairs : Vec<(f64, f64)> = weighted_values.into_iter().collect();
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        
+        let total_weight : f64 = pairs.iter().map(|p| p.1).sum();
+        let values : Vec<f64> = pairs.iter().map(|p| p.0).collect();
+        let weights : Vec<f64> = pairs.iter().map(|p| p.1 / total_weight).collect();
+        
+        let mut cumulative_weights = Vec::with_capacity(weights.len());
+        let mut cumulative = 0.0;
+        for w in weights.iter() {
+            cumulative_weights.push(cumulative);
+            cumulative += w;
+        }
+        
+        WeightedSnapshot {
+            values,
+            weights,
+            cumulative_weights
+        }
+    }
+    
+    /// The values, in ascending order.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+    
+    /// The number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    
+    /// Whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    
+    /// The smallest value.
+    pub fn min(&self) -> Option<f64> {
+        self.values.first().cloned()
+    }
+    
+    /// The largest value.
+    pub fn max(&self) -> Option<f64> {
+        self.values.last().cloned()
+    }
+    
+    /// The weighted mean of the values.
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.values.iter().zip(self.weights.iter()).map(|(v, w)| v * w).sum())
+    }
+    
+    /// The weighted standard deviation of the values.
+    pub fn stddev(&self) -> Option<f64> {
+        self.mean().map(|mean| {
+            let variance : f64 = self.values.iter().zip(self.weights.iter())
+                .map(|(v, w)| w * (v - mean) * (v - mean))
+                .sum();
+            variance.sqrt()
+        })
+    }
+    
+    /// The value at quantile `q` of the weighted distribution: the
+    /// largest value for which the total weight of the smaller values
+    /// is no more than `q`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not between 0 and 1.
+    pub fn quantile(&self, q : f64) -> Option<f64> {
+        assert!((0.0..=1.0).contains(&q), "quantile {} is not between 0 and 1", q);
+        
+        if self.is_empty() {
+            return None;
+        }
+        let position = self.cumulative_weights.iter().take_while(|&&c| c <= q).count();
+        Some(self.values[position.max(1) - 1])
+    }
+    
+    /// The weighted median of the values.
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::hash::{SeededHash, StableHash};
+
+/// A uniform sample of the distinct values in a stream, with the
+/// number of times each sampled value occurred.
+///
+/// Implements Gibbons' distinct sampling.  Each value is hashed, by
+/// its `StableHash` encoding with SipHash-2-4 keyed by a seed, and the
+/// sample holds the values whose hashes have at least `level` trailing
+/// zero bits, which is a fraction 2^-level of the distinct values,
+/// however often each one occurs.  When the sample grows beyond its
+/// capacity, the level is raised, evicting about half of the values.
+/// A sampled value has been in the sample since it first occurred, so
+/// its count of occurrences is exact.
+///
+/// Samplers with the same seed choose the same values on every
+/// platform and with every version of Rust, as `BottomK` sketches do.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::DistinctSampler;
+/// # fn main() {
+/// let mut sampler = DistinctSampler::new(100, 42);
+///
+/// // 10000 distinct values, the first of which occurs 100000 times
+/// for _ in 0..100000 {
+///     sampler.insert(0);
+/// }
+/// for value in 0..10000 {
+///     sampler.insert(value);
+/// }
+///
+/// assert!(sampler.len() <= 100);
+/// assert!(sampler.samples().all(|(value, count)| count == if *value == 0 { 100001 } else { 1 }));
+///
+/// let estimate = sampler.estimated_distinct();
+/// assert!(estimate > 7000.0 && estimate < 13000.0);
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DistinctSampler<T> {
+    capacity : usize,
+    hash : SeededHash,
+    level : u32,
+    values : HashMap<T, (u64, usize)>,
+    seen : usize
+}
+
+impl<T> DistinctSampler<T>
+    where T : StableHash + Hash + Eq
+{
+    /// Create an empty sampler that will hold at most `capacity`
+    /// distinct values, hashing values with the `seed`.
+    pub fn new(capacity : usize, seed : u64) -> Self {
+        DistinctSampler {
+            capacity,
+            hash: SeededHash::new(seed),
+            level: 0,
+            values: HashMap::new(),
+            seen: 0
+        }
+    }
+    
+    /// Offer an occurrence of a value to the sampler.
+    pub fn insert(&mut self, value : T) {
+        self.seen += 1;
+        
+        let hash = self.hash.stable_hash(&value);
+        if hash.trailing_zeros() < self.level {
+            return;
+        }
+        
+        self.values.entry(value).or_insert((hash, 0)).1 += 1;
+        
+        while self.values.len() > self.capacity {
+            self.level += 1;
+            let level = self.level;
+            self.values.retain(|_, &mut (hash, _)| hash.trailing_zeros() >= level);
+        }
+    }
+    
+    /// The sampled values, each with the number of times it occurred,
+    /// in arbitrary order.
+    pub fn samples(&self) -> impl Iterator<Item=(&T, usize)> {
+        self.values.iter().map(|(value, &(_, count))| (value, count))
+    }
+    
+    /// The number of times `value` occurred, if it is in the sample.
+    pub fn count(&self, value : &T) -> Option<usize> {
+        self.values.get(value).map(|&(_, count)| count)
+    }
+    
+    /// The number of distinct values in the sample.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    
+    /// Whether the sample is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    
+    /// The number of values, including repeats, offered to the sampler.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+    
+    /// The number of times the level has been raised.  The sample holds
+    /// a fraction 2^-level of the distinct values.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+    
+    /// The fraction of the distinct values that are in the sample.
+    pub fn sampling_rate(&self) -> f64 {
+        0.5f64.powi(self.level as i32)
+    }
+    
+    /// An estimate of the number of distinct values in the stream,
+    /// which is exact if the level has never been raised.
+    pub fn estimated_distinct(&self) -> f64 {
+        self.values.len() as f64 / self.sampling_rate()
+    }
+    
+    /// An estimate of the number of occurrences, in the stream, of the
+    /// values for which `predicate` is true.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::DistinctSampler;
+    /// # fn main() {
+    /// let mut sampler = DistinctSampler::new(1000, 7);
+    ///
+    /// for value in 0..100000u32 {
+    ///     sampler.insert(value % 20000);
+    /// }
+    ///
+    /// // 10000 distinct even values occur 5 times each
+    /// let estimate = sampler.estimated_occurrences(|value| value % 2 == 0);
+    /// assert!(estimate > 40000.0 && estimate < 60000.0);
+    /// # }
+    /// ```
+    pub fn estimated_occurrences<F>(&self, mut predicate : F) -> f64
+        where F : FnMut(&T) -> bool
+    {
+        let sampled : usize = self.samples()
+            .filter(|&(value, _)| predicate(value))
+            .map(|(_, count)| count)
+            .sum();
+        sampled as f64 / self.sampling_rate()
+    }
+    
+    /// Consume the sampler, returning the sampled values, each with the
+    /// number of times it occurred.
+    pub fn into_samples(self) -> HashMap<T, usize> {
+        self.values.into_iter().map(|(value, (_, count))| (value, count)).collect()
+    }
+}
+
+impl<T> Extend<T> for DistinctSampler<T>
+    where T : StableHash + Hash + Eq
+{
+    fn extend<I : IntoIterator<Item=T>>(&mut self, iter : I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+/// Estimates of totals, means and proportions over a whole population
+/// from a simple random sample of it, such as the contents of a
+/// reservoir together with the number of items the reservoir has seen.
+///
+/// Each item in a sample of n items from a population of N items was
+/// included with probability n/N, so weighting each sampled value by
+/// N/n (the Horvitz–Thompson estimator) gives an unbiased estimate of
+/// the population total.  The variances include the finite population
+/// correction, 1 - n/N, and so fall to zero when the sample is the
+/// whole population.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::{Estimator, sample_into};
+/// # fn main() {
+/// let mut samples = Vec::new();
+/// let seen = sample_into(&mut samples, &mut thread_rng(), 1000, 0..100000);
+/// let estimator = Estimator::new(&samples, seen).unwrap();
+///
+/// let even = estimator.proportion(|&x| x % 2 == 0);
+/// let (low, high) = even.confidence_interval(0.999999);
+///
+/// assert!(low < 0.5 && 0.5 < high);
+///
+/// let total = estimator.total(|&x| x as f64);
+/// let (low, high) = total.confidence_interval(0.999999);
+///
+/// assert!(low < 4999950000.0 && 4999950000.0 < high);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Estimator<'a, T : 'a> {
+    samples : &'a [T],
+    population : usize
+}
+
+impl<'a, T> Estimator<'a, T> {
+    /// Estimate from `samples`, a simple random sample without
+    /// replacement of a population of `population` items, or return
+    /// `None` if there are no samples to estimate from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more samples than items in the population.
+    pub fn new(samples : &'a [T], population : usize) -> Option<Self> {
+        assert!(samples.len() <= population, "sample of {} items is larger than the population of {}", samples.len(), population);
+        
+        if samples.is_empty() {
+            return None;
+        }
+        Some(Estimator {
+            samples,
+            population
+        })
+    }
+    
+    /// The number of items in the sample.
+    pub fn sample_size(&self) -> usize {
+        self.samples.len()
+    }
+    
+    /// The number of items in the population.
+    pub fn population(&self) -> usize {
+        self.population
+    }
+    
+    /// Estimate the mean of `value` over the population.
+    pub fn mean<F>(&self, mut value : F) -> Estimate
+        where F : FnMut(&T) -> f64
+    {
+        let n = self.samples.len() as f64;
+        let mean = self.samples.iter().map(&mut value).sum::<f64>() / n;
+        
+        let variance = if self.samples.len() == self.population {
+            0.0
+        } else if self.samples.len() < 2 {
+            f64::INFINITY
+        } else {
+            let sum_of_squares : f64 = self.samples.iter()
+                .map(|item| (value(item) - mean).powi(2))
+                .sum();
+            let sample_variance = sum_of_squares / (n - 1.0);
+            let correction = 1.0 - n / self.population as f64;
+            
+            correction * sample_variance / n
+        };
+        
+        Estimate {
+            value: mean,
+            variance
+        }
+    }
+    
+    /// Estimate the total of `value` over the population.
+    pub fn total<F>(&self, value : F) -> Estimate
+        where F : FnMut(&T) -> f64
+    {
+        let population = self.population as f64;
+        let mean = self.mean(value);
+        
+        Estimate {
+            value: mean.value * population,
+            variance: mean.variance * population * population
+        }
+    }
+    
+    /// Estimate the proportion of the population for which `predicate`
+    /// holds.
+    pub fn proportion<P>(&self, mut predicate : P) -> Estimate
+        where P : FnMut(&T) -> bool
+    {
+        self.mean(|item| if predicate(item) { 1.0 } else { 0.0 })
+    }
+    
+    /// Estimate the number of items in the population for which
+    /// `predicate` holds.
+    pub fn count<P>(&self, mut predicate : P) -> Estimate
+        where P : FnMut(&T) -> bool
+    {
+        self.total(|item| if predicate(item) { 1.0 } else { 0.0 })
+    }
+}
+
+/// An unbiased estimate of a population statistic, with its variance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    value : f64,
+    variance : f64
+}
+
+impl Estimate {
+    /// The estimated value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+    
+    /// The estimated variance of the estimate.
+    ///
+    /// Infinite when the sample has a single item and is smaller than
+    /// the population, since there is then no estimate of the spread.
+    pub fn variance(&self) -> f64 {
+        self.variance
+    }
+    
+    /// The estimated standard deviation of the estimate.
+    pub fn standard_error(&self) -> f64 {
+        self.variance.sqrt()
+    }
+    
+    /// A two-sided confidence interval containing the true value with
+    /// approximately the given probability, such as 0.95.
+    ///
+    /// Assumes the estimate is normally distributed, which is a good
+    /// approximation when the sample has more than a few tens of items
+    /// and the values are not heavily skewed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the level is not strictly between 0 and 1.
+    pub fn confidence_interval(&self, level : f64) -> (f64, f64) {
+        assert!(level > 0.0 && level < 1.0, "confidence level {} is not between 0 and 1", level);
+        
+        let margin = inverse_normal_cdf(0.5 + level / 2.0) * self.standard_error();
+        
+        (self.value - margin, self.value + margin)
+    }
+}
+
+/// The quantile function of the standard normal distribution, by
+/// Acklam's rational approximation, with a relative error below
+/// 1.15e-9.
+fn inverse_normal_cdf(p : f64) -> f64 {
+    const A : [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02,
+                          1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+    const B : [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02,
+                          6.680131188771972e+01, -1.328068155288572e+01];
+    const C : [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00,
+                          -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+    const D : [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00,
+                          3.754408661907416e+00];
+    const LOW : f64 = 0.02425;
+    
+    let tail = |q : f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) /
+            ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q /
+            (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
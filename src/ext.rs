@@ -0,0 +1,180 @@
+use rand::{Rng, ThreadRng, thread_rng};
+
+use super::sequential::sample_of_length;
+use super::{BernoulliSample, WeightError, Summary,
+            sample, sample_indexed, sample_in_order, sample_with_replacement, sample_summary,
+            weighted_sample_exp_jumps_by};
+
+/// Reservoir sampling methods for all iterators, so that sampling can
+/// be chained after adapters such as `filter` and `map`.
+///
+/// The methods delegate to the functions of the same purpose.  Those
+/// that do not take a random number generator use `thread_rng()`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::SampleExt;
+/// # fn main() {
+/// let samples = (0..100).filter(|n| n % 2 == 0).map(|n| n * 10).reservoir_sample(4);
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.iter().all(|n| n % 20 == 0));
+/// # }
+/// ```
+pub trait SampleExt : Iterator + Sized {
+    /// Return a random sample of at most `sample_size` items.
+    ///
+    /// If the iterator reports its exact length in its `size_hint`, as
+    /// iterators over slices and ranges do, samples it with Algorithm
+    /// D, as `sequential_sample` does, and returns the sampled items in
+    /// the order in which they occur.  Otherwise, samples it with
+    /// Algorithm R, as `sample` does.
+    ///
+    /// Since `size_hint` cannot be relied on, if the iterator turns out
+    /// to be longer than it reported, the rest of it is sampled with
+    /// Algorithm R.  The sample is still uniform, but no longer in
+    /// order.  If it turns out to be shorter, the sample may hold fewer
+    /// than `sample_size` items even though there were enough.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::SampleExt;
+    /// # fn main() {
+    /// let samples = (0..1000000).reservoir_sample(4);
+    ///
+    /// assert!(samples.windows(2).all(|w| w[0] < w[1]));
+    /// # }
+    /// ```
+    fn reservoir_sample(self, sample_size : usize) -> Vec<Self::Item> {
+        self.reservoir_sample_with(&mut thread_rng(), sample_size)
+    }
+    
+    /// Return a random sample of at most `sample_size` items, chosen
+    /// with random numbers from `rng`, as `reservoir_sample` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate rand;
+    /// # extern crate reservoir;
+    /// # use rand::{SeedableRng, XorShiftRng};
+    /// # use reservoir::SampleExt;
+    /// # fn main() {
+    /// let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    ///
+    /// let samples = "the quick brown fox".split(' ').reservoir_sample_with(&mut rng, 2);
+    ///
+    /// assert_eq!(2, samples.len());
+    /// # }
+    /// ```
+    fn reservoir_sample_with<RNG>(self, rng : &mut RNG, sample_size : usize) -> Vec<Self::Item>
+        where RNG : Rng
+    {
+        match self.size_hint() {
+            (lower, Some(upper)) if lower == upper => {
+                let mut samples = Vec::with_capacity(sample_size.min(lower));
+                sample_of_length(&mut samples, rng, sample_size, lower, self);
+                samples
+            }
+            _ => sample(rng, sample_size, self)
+        }
+    }
+    
+    /// Return a random sample of at most `sample_size` items, with
+    /// their positions in the iterator, as `sample_indexed` does.
+    fn reservoir_sample_indexed(self, sample_size : usize) -> Vec<(usize, Self::Item)> {
+        sample_indexed(&mut thread_rng(), sample_size, self)
+    }
+    
+    /// Return a random sample of at most `sample_size` items, with
+    /// their positions in the iterator, in the order they occurred, as
+    /// `sample_in_order` does.
+    fn reservoir_sample_in_order(self, sample_size : usize) -> Vec<(usize, Self::Item)> {
+        sample_in_order(&mut thread_rng(), sample_size, self)
+    }
+    
+    /// Return exactly `sample_size` items drawn with replacement, as
+    /// `sample_with_replacement` does.
+    fn reservoir_sample_with_replacement(self, sample_size : usize) -> Vec<Self::Item>
+        where Self::Item : Clone
+    {
+        sample_with_replacement(&mut thread_rng(), sample_size, self)
+    }
+    
+    /// Return a summary of the iterator holding a random sample of at
+    /// most `sample_size` items, as `sample_summary` does.
+    fn reservoir_summary(self, sample_size : usize) -> Summary<Self::Item> {
+        sample_summary(&mut thread_rng(), sample_size, self)
+    }
+    
+    /// Return a random sample of at most `sample_size` items, where the
+    /// probability of sampling an item is proportional to the weight
+    /// calculated by the `weight` function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::SampleExt;
+    /// # fn main() {
+    /// let flows = vec![("http", 1500), ("dns", 80), ("ssh", 400)];
+    ///
+    /// let samples = flows.into_iter().weighted_reservoir_sample(2, |&(_, bytes)| bytes as f64).unwrap();
+    ///
+    /// assert_eq!(2, samples.len());
+    /// # }
+    /// ```
+    fn weighted_reservoir_sample<F>(self, sample_size : usize, weight : F) -> Result<Vec<Self::Item>, WeightError>
+        where F : FnMut(&Self::Item) -> f64
+    {
+        self.weighted_reservoir_sample_with(&mut thread_rng(), sample_size, weight)
+    }
+    
+    /// Return a random sample of at most `sample_size` items, chosen
+    /// with random numbers from `rng`, where the probability of
+    /// sampling an item is proportional to the weight calculated by
+    /// the `weight` function.
+    fn weighted_reservoir_sample_with<RNG, F>(self, rng : &mut RNG, sample_size : usize, weight : F) -> Result<Vec<Self::Item>, WeightError>
+        where RNG : Rng,
+              F : FnMut(&Self::Item) -> f64
+    {
+        weighted_sample_exp_jumps_by(rng, sample_size, self, weight)
+    }
+    
+    /// Return an iterator over a sample that includes each item
+    /// independently with the given `probability`, as
+    /// `BernoulliSample` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate reservoir;
+    /// # use reservoir::SampleExt;
+    /// # fn main() {
+    /// let sample = (0..100000).bernoulli_sample(0.01);
+    /// let scale_factor = sample.scale_factor();
+    ///
+    /// let estimated_even = sample.filter(|n| n % 2 == 0).count() as f64 * scale_factor;
+    ///
+    /// assert!(estimated_even > 40000.0 && estimated_even < 60000.0);
+    /// # }
+    /// ```
+    fn bernoulli_sample(self, probability : f64) -> BernoulliSample<Self, ThreadRng> {
+        self.bernoulli_sample_with(thread_rng(), probability)
+    }
+    
+    /// Return an iterator over a sample that includes each item
+    /// independently with the given `probability`, chosen with random
+    /// numbers from `rng`.
+    fn bernoulli_sample_with<RNG>(self, rng : RNG, probability : f64) -> BernoulliSample<Self, RNG>
+        where RNG : Rng
+    {
+        BernoulliSample::new(self, probability, rng)
+    }
+}
+
+impl<I : Iterator> SampleExt for I {}
@@ -0,0 +1,316 @@
+use std::hash::Hasher;
+
+use super::SeededRng;
+use rand::Rng;
+
+/// A type whose values hash to the same bytes on every platform and
+/// with every version of Rust, so that `BottomK` sketches and
+/// `DistinctSampler`s built on different machines choose the same
+/// keys.
+///
+/// The standard library's `Hash` implementations make no such promise:
+/// the bytes that strings, slices and `usize`s write to a hasher may
+/// change between compiler versions and differ between platforms.
+/// Implementations of `StableHash` write a fixed encoding with
+/// `Hasher::write` alone:
+///
+/// * integers as their little endian bytes, with `usize` and `isize`
+///   widened to 64 bits;
+/// * `bool` as one byte, 0 or 1, and `char` as a `u32`;
+/// * strings as their length in bytes, as a `u64`, followed by their
+///   UTF-8 bytes;
+/// * slices, arrays and `Vec`s as their length, as a `u64`, followed by
+///   their elements;
+/// * `Option`s as a byte, 0 for `None` and 1 for `Some`, followed by
+///   the value;
+/// * tuples as their elements in order, and references as the value
+///   referred to.
+///
+/// Implementations for other types should write an encoding that is
+/// fixed in the same way, such as by hashing their fields in order.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use std::hash::Hasher;
+/// # use reservoir::{BottomK, StableHash};
+/// # fn main() {
+/// struct UserId(u64);
+///
+/// impl StableHash for UserId {
+///     fn stable_hash<H : Hasher>(&self, state : &mut H) {
+///         self.0.stable_hash(state);
+///     }
+/// }
+///
+/// let sketch = BottomK::<UserId>::new(10, 42);
+/// assert_eq!(sketch.hash_of(&1234u64), sketch.hash_of(&UserId(1234)));
+/// # }
+/// ```
+pub trait StableHash {
+    /// Write the value's encoding to the hasher.
+    fn stable_hash<H : Hasher>(&self, state : &mut H);
+}
+
+macro_rules! stable_hash_le_bytes {
+    ($($t:ty),*) => {
+        $(
+            impl StableHash for $t {
+                fn stable_hash<H : Hasher>(&self, state : &mut H) {
+                    state.write(&self.to_le_bytes());
+                }
+            }
+        )*
+    }
+}
+
+stable_hash_le_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl StableHash for usize {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        (*self as u64).stable_hash(state);
+    }
+}
+
+impl StableHash for isize {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        (*self as i64).stable_hash(state);
+    }
+}
+
+impl StableHash for bool {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        (*self as u8).stable_hash(state);
+    }
+}
+
+impl StableHash for char {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        (*self as u32).stable_hash(state);
+    }
+}
+
+impl StableHash for str {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        self.len().stable_hash(state);
+        state.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        self.as_str().stable_hash(state);
+    }
+}
+
+impl<T : StableHash> StableHash for [T] {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        self.len().stable_hash(state);
+        for item in self {
+            item.stable_hash(state);
+        }
+    }
+}
+
+impl<T : StableHash, const N : usize> StableHash for [T; N] {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        self[..].stable_hash(state);
+    }
+}
+
+impl<T : StableHash> StableHash for Vec<T> {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        self[..].stable_hash(state);
+    }
+}
+
+impl<T : StableHash> StableHash for Option<T> {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        match *self {
+            None => 0u8.stable_hash(state),
+            Some(ref value) => {
+                1u8.stable_hash(state);
+                value.stable_hash(state);
+            }
+        }
+    }
+}
+
+impl<T : StableHash + ?Sized> StableHash for &T {
+    fn stable_hash<H : Hasher>(&self, state : &mut H) {
+        (**self).stable_hash(state);
+    }
+}
+
+macro_rules! stable_hash_tuple {
+    ($($name:ident)+) => {
+        impl<$($name : StableHash),+> StableHash for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn stable_hash<H : Hasher>(&self, state : &mut H) {
+                let ($(ref $name,)+) = *self;
+                $($name.stable_hash(state);)+
+            }
+        }
+    }
+}
+
+stable_hash_tuple!(A);
+stable_hash_tuple!(A B);
+stable_hash_tuple!(A B C);
+stable_hash_tuple!(A B C D);
+stable_hash_tuple!(A B C D E);
+stable_hash_tuple!(A B C D E F);
+
+// SipHash-2-4, keyed by 128 bits derived from a seed.  Unlike the
+// hashers in the standard library, its output for a given sequence of
+// bytes is guaranteed not to change between Rust versions.  It writes
+// integers as little endian and usizes as 64 bits, but the bytes that
+// other types' `Hash` implementations write may still vary, which is
+// why keys are hashed by their `StableHash` encoding instead.
+#[derive(Clone, Debug)]
+pub(crate) struct SipHasher24 {
+    v0 : u64,
+    v1 : u64,
+    v2 : u64,
+    v3 : u64,
+    tail : u64,
+    ntail : usize,
+    length : usize
+}
+
+impl SipHasher24 {
+    pub(crate) fn new(k0 : u64, k1 : u64) -> Self {
+        SipHasher24 {
+            v0: k0 ^ 0x736f6d6570736575,
+            v1: k1 ^ 0x646f72616e646f6d,
+            v2: k0 ^ 0x6c7967656e657261,
+            v3: k1 ^ 0x7465646279746573,
+            tail: 0,
+            ntail: 0,
+            length: 0
+        }
+    }
+    
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13);
+        self.v1 ^= self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16);
+        self.v3 ^= self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21);
+        self.v3 ^= self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17);
+        self.v1 ^= self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+    
+    fn compress(&mut self, m : u64) {
+        self.v3 ^= m;
+        self.round();
+        self.round();
+        self.v0 ^= m;
+    }
+}
+
+impl Hasher for SipHasher24 {
+    fn write(&mut self, bytes : &[u8]) {
+        self.length += bytes.len();
+        
+        for &b in bytes {
+            self.tail |= (b as u64) << (8 * self.ntail);
+            self.ntail += 1;
+            if self.ntail == 8 {
+                let m = self.tail;
+                self.compress(m);
+                self.tail = 0;
+                self.ntail = 0;
+            }
+        }
+    }
+    
+    fn write_u16(&mut self, i : u16) {
+        self.write(&i.to_le_bytes());
+    }
+    
+    fn write_u32(&mut self, i : u32) {
+        self.write(&i.to_le_bytes());
+    }
+    
+    fn write_u64(&mut self, i : u64) {
+        self.write(&i.to_le_bytes());
+    }
+    
+    fn write_u128(&mut self, i : u128) {
+        self.write(&i.to_le_bytes());
+    }
+    
+    fn write_usize(&mut self, i : usize) {
+        self.write_u64(i as u64);
+    }
+    
+    fn write_i16(&mut self, i : i16) {
+        self.write_u16(i as u16);
+    }
+    
+    fn write_i32(&mut self, i : i32) {
+        self.write_u32(i as u32);
+    }
+    
+    fn write_i64(&mut self, i : i64) {
+        self.write_u64(i as u64);
+    }
+    
+    fn write_i128(&mut self, i : i128) {
+        self.write_u128(i as u128);
+    }
+    
+    fn write_isize(&mut self, i : isize) {
+        self.write_u64(i as u64);
+    }
+    
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        
+        let b = ((self.length as u64 & 0xff) << 56) | self.tail;
+        state.compress(b);
+        state.v2 ^= 0xff;
+        for _ in 0..4 {
+            state.round();
+        }
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
+
+// Hashes keys with SipHash-2-4, keyed by a seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SeededHash {
+    seed : u64,
+    k0 : u64,
+    k1 : u64
+}
+
+impl SeededHash {
+    pub(crate) fn new(seed : u64) -> Self {
+        let mut rng = SeededRng::new(seed);
+        SeededHash {
+            seed,
+            k0: rng.next_u64(),
+            k1: rng.next_u64()
+        }
+    }
+    
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+    
+    pub(crate) fn stable_hash<K : StableHash + ?Sized>(&self, key : &K) -> u64 {
+        let mut hasher = SipHasher24::new(self.k0, self.k1);
+        key.stable_hash(&mut hasher);
+        hasher.finish()
+    }
+}
@@ -0,0 +1,111 @@
+use rand::Rng;
+
+use super::sequential::Skips;
+
+// Floyd's algorithm, checking membership by linear search, is faster
+// than Algorithm D for samples up to this size.
+const FLOYD_LIMIT : usize = 32;
+
+/// Return `sample_size` distinct indices chosen uniformly at random
+/// from `0..len`, in ascending order, or all the indices if
+/// `sample_size` is at least `len`.
+///
+/// Uses Floyd's algorithm for small samples and Vitter's Algorithm D
+/// for larger ones, both of which draw O(k) random numbers for a
+/// sample of k indices, however large `len` is.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_indices;
+/// # fn main() {
+/// let indices = sample_indices(&mut thread_rng(), 5, 1000000000);
+///
+/// assert_eq!(5, indices.len());
+/// assert!(indices.windows(2).all(|w| w[0] < w[1]));
+/// assert!(indices.iter().all(|&i| i < 1000000000));
+///
+/// assert_eq!(vec![0, 1, 2], sample_indices(&mut thread_rng(), 10, 3));
+/// # }
+/// ```
+///
+/// Every index is equally likely to be chosen:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_indices;
+/// # fn main() {
+/// let mut counts = [0usize; 200];
+///
+/// for _ in 0..1000 {
+///     for i in sample_indices(&mut thread_rng(), 4, 200).into_iter()
+///         .chain(sample_indices(&mut thread_rng(), 60, 200))
+///     {
+///         counts[i] += 1;
+///     }
+/// }
+///
+/// // Each index is expected to be chosen 320 times
+/// assert!(counts.iter().all(|&c| c > 240 && c < 400));
+/// # }
+/// ```
+pub fn sample_indices<RNG>(rng : &mut RNG, sample_size : usize, len : usize) -> Vec<usize>
+    where RNG : Rng
+{
+    if sample_size >= len {
+        return (0..len).collect();
+    }
+    
+    if sample_size <= FLOYD_LIMIT {
+        let mut indices = Vec::with_capacity(sample_size);
+        for j in len - sample_size..len {
+            let t = rng.gen_range(0, j + 1);
+            indices.push(if indices.contains(&t) { j } else { t });
+        }
+        indices.sort();
+        indices
+    } else {
+        let mut indices = Vec::with_capacity(sample_size);
+        let mut skips = Skips::new(rng, sample_size, len);
+        let mut next = 0;
+        while let Some(skip) = skips.next(rng) {
+            next += skip;
+            indices.push(next);
+            next += 1;
+        }
+        indices
+    }
+}
+
+/// Return references to a random sample of a known maximum size from
+/// a slice, in the order in which they occur in the slice, without
+/// moving or cloning the items.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_slice;
+/// # fn main() {
+/// let names = vec!["alice".to_string(), "bob".to_string(), "carol".to_string(), "dave".to_string()];
+///
+/// let samples : Vec<&String> = sample_slice(&mut thread_rng(), 2, &names);
+///
+/// assert_eq!(2, samples.len());
+/// assert!(samples.iter().all(|s| names.contains(s)));
+/// # }
+/// ```
+pub fn sample_slice<'a, T, RNG>(rng : &mut RNG, sample_size : usize, slice : &'a [T]) -> Vec<&'a T>
+    where RNG : Rng
+{
+    sample_indices(rng, sample_size, slice.len()).into_iter()
+        .map(|i| &slice[i])
+        .collect()
+}
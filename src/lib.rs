@@ -2,11 +2,75 @@
 //! size from an iterator of unknown length.
 //!
 //! Implements Jeffrey Vitter's Algorithm R (see
-//! https://en.wikipedia.org/wiki/Reservoir_sampling)
+//! https://en.wikipedia.org/wiki/Reservoir_sampling) and Kim-Hung Li's
+//! Algorithm L, which skips over elements that will not be sampled
+//! instead of drawing a random number for every element.  Sources of
+//! known length can be sampled in order with Vitter's Algorithm D.
+//!
+//! Weighted sampling, in which the probability of sampling an element
+//! is proportional to its weight, implements Efraimidis and Spirakis'
+//! Algorithms A-Res and A-ExpJ.
 
 extern crate rand;
+#[cfg(feature = "futures")]
+extern crate futures;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use rand::{Rng, Open01};
+
+mod bernoulli;
+mod bottomk;
+mod decay;
+mod distinct;
+mod estimate;
+mod ext;
+mod hash;
+mod index;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pairing;
+mod reservoir;
+mod seeded;
+mod sequential;
+mod snapshot;
+mod stratified;
+#[cfg(feature = "futures")]
+mod stream;
+mod summary;
+mod varopt;
+mod weighted;
+mod window;
 
-use rand::Rng;
+pub use bernoulli::{BernoulliSample, bernoulli_sample};
+pub use bottomk::BottomK;
+pub use decay::{DecayingReservoir, WeightedSnapshot};
+pub use distinct::DistinctSampler;
+pub use estimate::{Estimator, Estimate};
+pub use ext::SampleExt;
+pub use hash::StableHash;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_sample, par_sample_summary};
+pub use index::{sample_indices, sample_slice};
+pub use pairing::RandomPairingReservoir;
+pub use reservoir::Reservoir;
+pub use seeded::{SeededRng, seeded_sample, seeded_sample_into};
+pub use sequential::{sequential_sample, sequential_sample_into};
+pub use snapshot::{Snapshot, Interpolation};
+pub use stratified::{StratifiedReservoir, stratified_sample};
+#[cfg(feature = "futures")]
+pub use stream::{sample_stream, sample_stream_into};
+pub use summary::{Summary, sample_summary};
+pub use varopt::VarOpt;
+pub use weighted::{WeightError, PrioritySample, priority_sample,
+                   weighted_sample, weighted_sample_into, weighted_sample_by,
+                   weighted_sample_exp_jumps, weighted_sample_exp_jumps_into, weighted_sample_exp_jumps_by};
+pub use window::{WindowReservoir, TimeWindowReservoir};
 
 /// Return a random sample of a known maximum size from an iterator of
 /// unknown length.
@@ -45,7 +109,6 @@ use rand::Rng;
 /// assert_eq!(expected_samples, samples);
 /// # }
 /// ```
-
 pub fn sample<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<I::Item>
     where I : Iterator,
           RNG : Rng
@@ -57,6 +120,10 @@ pub fn sample<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<I::I
 
 /// Collect a random sample of a known maximum size from an iterator
 /// of unknown length into an existing Vec.
+///
+/// Returns the number of elements in the iterator, which is needed to
+/// extrapolate from the sample to the whole iterator (see
+/// `Estimator`).
 /// 
 /// # Examples
 ///
@@ -71,8 +138,9 @@ pub fn sample<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<I::I
 /// let iter = 0..10;
 /// let mut samples : Vec<i32> = Vec::new();
 /// 
-/// sample_into(&mut samples, &mut thread_rng(), 4, iter);
+/// let count = sample_into(&mut samples, &mut thread_rng(), 4, iter);
 /// 
+/// assert_eq!(10, count);
 /// assert_eq!(4, samples.len());
 /// assert!(samples.iter().all(|e| *e >= 0 && *e < 10));
 /// # }
@@ -97,8 +165,7 @@ pub fn sample<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<I::I
 /// assert!(samples[2..].iter().all(|e| *e >= 0 && *e < 10));
 /// # }
 /// ```
-
-pub fn sample_into<I, RNG>(samples : &mut Vec<I::Item>, rng : &mut RNG, sample_size : usize, iter : I)
+pub fn sample_into<I, RNG>(samples : &mut Vec<I::Item>, rng : &mut RNG, sample_size : usize, iter : I) -> usize
     where I : Iterator,
           RNG : Rng
 {
@@ -111,11 +178,351 @@ pub fn sample_into<I, RNG>(samples : &mut Vec<I::Item>, rng : &mut RNG, sample_s
         if count <= sample_size {
             samples.push(element);
         } else {
-            let index = rng.gen_range(0, count);
-            if index < sample_size {
-                samples[original_length+index] = element;
+            replace(&mut samples[original_length..], rng, count, element);
+        }
+    }
+    
+    count
+}
+
+// The step of Algorithm R that is performed for the count'th element
+// of a stream once the reservoir is full.
+fn replace<T, RNG>(reservoir : &mut [T], rng : &mut RNG, count : usize, element : T)
+    where RNG : Rng
+{
+    let index = rng.gen_range(0, count);
+    if index < reservoir.len() {
+        reservoir[index] = element;
+    }
+}
+
+/// Return a random sample of a known maximum size from an iterator of
+/// unknown length, with the position in the iterator, counting from
+/// zero, of each sampled item.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_indexed;
+/// # fn main() {
+/// let lines = vec!["starting", "listening", "request", "error", "stopping"];
+///
+/// let samples = sample_indexed(&mut thread_rng(), 2, lines.iter());
+///
+/// assert_eq!(2, samples.len());
+/// assert!(samples.iter().all(|&(i, line)| lines[i] == *line));
+/// # }
+/// ```
+pub fn sample_indexed<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<(usize, I::Item)>
+    where I : Iterator,
+          RNG : Rng
+{
+    sample(rng, sample_size, iter.enumerate())
+}
+
+/// Collect a random sample of a known maximum size from an iterator
+/// of unknown length into an existing Vec, with the position in the
+/// iterator, counting from zero, of each sampled item.
+///
+/// Returns the number of items in the iterator.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_indexed_into;
+/// # fn main() {
+/// let mut samples = vec![(0, 'a')];
+///
+/// sample_indexed_into(&mut samples, &mut thread_rng(), 2, "xyz".chars());
+///
+/// assert_eq!(3, samples.len());
+/// assert_eq!((0, 'a'), samples[0]);
+/// assert!(samples[1..].iter().all(|&(i, c)| "xyz".chars().nth(i) == Some(c)));
+/// # }
+/// ```
+pub fn sample_indexed_into<I, RNG>(samples : &mut Vec<(usize, I::Item)>, rng : &mut RNG, sample_size : usize, iter : I) -> usize
+    where I : Iterator,
+          RNG : Rng
+{
+    sample_into(samples, rng, sample_size, iter.enumerate())
+}
+
+/// Return a random sample of a known maximum size from an iterator of
+/// unknown length, with the position in the iterator of each sampled
+/// item, in the order the items occurred in the iterator.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_in_order;
+/// # fn main() {
+/// let samples = sample_in_order(&mut thread_rng(), 4, 100..200);
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.windows(2).all(|w| w[0].0 < w[1].0));
+/// assert!(samples.iter().all(|&(i, e)| e == i + 100));
+/// # }
+/// ```
+pub fn sample_in_order<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<(usize, I::Item)>
+    where I : Iterator,
+          RNG : Rng
+{
+    let mut samples = sample_indexed(rng, sample_size, iter);
+    samples.sort_by_key(|&(i, _)| i);
+    samples
+}
+
+/// Return a random sample of a known maximum size from an iterator of
+/// unknown length, using Algorithm L.
+///
+/// Produces samples with the same distribution as `sample`, but
+/// calculates how many elements to skip between replacements, rather
+/// than drawing a random number for every element.  It makes
+/// O(k(1 + log(n/k))) random draws, rather than n, when sampling k
+/// elements from n, so is much faster than `sample` when drawing
+/// random numbers dominates the cost of iterating.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::skip_sample;
+/// # fn main() {
+/// let iter = 0..1000000;
+///
+/// let samples = skip_sample(&mut thread_rng(), 4, iter);
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.iter().all(|e| *e >= 0 && *e < 1000000));
+/// # }
+/// ```
+///
+/// If the sampled iterator contains fewer items than the sample_size,
+/// all items are returned.
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::skip_sample;
+/// # fn main() {
+/// let samples : Vec<i32> = skip_sample(&mut thread_rng(), 20, 0..10);
+/// let expected_samples : Vec<i32> = (0..10).collect();
+///
+/// assert_eq!(expected_samples, samples);
+/// # }
+/// ```
+pub fn skip_sample<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<I::Item>
+    where I : Iterator,
+          RNG : Rng
+{
+    let mut samples = Vec::<I::Item>::with_capacity(sample_size);
+    skip_sample_into(&mut samples, rng, sample_size, iter);
+    samples
+}
+
+/// Collect a random sample of a known maximum size from an iterator
+/// of unknown length into an existing Vec, using Algorithm L.
+///
+/// Returns the number of elements in the iterator.
+///
+/// # Examples
+///
+/// Preserves any elements already in the vector:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::skip_sample_into;
+/// # fn main() {
+/// let mut samples : Vec<i32> = vec![99,100];
+///
+/// let count = skip_sample_into(&mut samples, &mut thread_rng(), 4, 0..10);
+///
+/// assert_eq!(10, count);
+/// assert_eq!(6, samples.len());
+/// assert_eq!(99, samples[0]);
+/// assert_eq!(100, samples[1]);
+/// assert!(samples[2..].iter().all(|e| *e >= 0 && *e < 10));
+/// # }
+/// ```
+///
+/// Every element is equally likely to be sampled:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::skip_sample_into;
+/// # fn main() {
+/// let mut counts = [0usize; 10];
+/// let mut samples = Vec::new();
+///
+/// for _ in 0..10000 {
+///     samples.clear();
+///     skip_sample_into(&mut samples, &mut thread_rng(), 3, 0..10);
+///     for e in samples.iter() {
+///         counts[*e] += 1;
+///     }
+/// }
+///
+/// // Each element is expected to be sampled 3000 times
+/// assert!(counts.iter().all(|&c| c > 2600 && c < 3400));
+/// # }
+/// ```
+pub fn skip_sample_into<I, RNG>(samples : &mut Vec<I::Item>, rng : &mut RNG, sample_size : usize, mut iter : I) -> usize
+    where I : Iterator,
+          RNG : Rng
+{
+    if sample_size == 0 {
+        return iter.count();
+    }
+    
+    let original_length = samples.len();
+    
+    samples.extend(iter.by_ref().take(sample_size));
+    let mut count = samples.len() - original_length;
+    if count < sample_size {
+        return count;
+    }
+    
+    let k = sample_size as f64;
+    let mut w = (open01(rng).ln() / k).exp();
+    
+    loop {
+        let skip = (open01(rng).ln() / (-w).ln_1p()).floor();
+        
+        // Skip one element at a time, rather than with `nth`, so as to
+        // count the elements skipped if the iterator ends.
+        let mut skipped : usize = 0;
+        let element = loop {
+            match iter.next() {
+                Some(element) if skipped as f64 >= skip => break element,
+                Some(_) => skipped += 1,
+                None => return count + skipped
             }
+        };
+        count += skipped + 1;
+        
+        let index = rng.gen_range(0, sample_size);
+        samples[original_length+index] = element;
+        w *= (open01(rng).ln() / k).exp();
+    }
+}
+
+/// Return a random sample of exactly `sample_size` items, drawn with
+/// replacement, from an iterator of unknown length.
+///
+/// Each item of the result is an independent uniform draw from the
+/// whole iterator, so the sample may contain duplicates.  Equivalent
+/// to running `sample_size` independent reservoirs of one item each,
+/// but rather than drawing random numbers for every item, calculates
+/// when each reservoir will next be replaced and skips to the next
+/// replacement with `Iterator::nth`.
+///
+/// Returns an empty Vec if the iterator is empty.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_with_replacement;
+/// # fn main() {
+/// let samples = sample_with_replacement(&mut thread_rng(), 20, 0..10);
+///
+/// assert_eq!(20, samples.len());
+/// assert!(samples.iter().all(|e| *e >= 0 && *e < 10));
+///
+/// assert_eq!(vec!['x', 'x', 'x'], sample_with_replacement(&mut thread_rng(), 3, Some('x').into_iter()));
+/// assert!(sample_with_replacement(&mut thread_rng(), 3, None::<char>.into_iter()).is_empty());
+/// # }
+/// ```
+///
+/// Every item is equally likely to be drawn:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_with_replacement;
+/// # fn main() {
+/// let mut counts = [0usize; 10];
+///
+/// for e in sample_with_replacement(&mut thread_rng(), 100000, 0..10) {
+///     counts[e] += 1;
+/// }
+///
+/// // Each element is expected to be drawn 10000 times
+/// assert!(counts.iter().all(|&c| c > 9400 && c < 10600));
+/// # }
+/// ```
+pub fn sample_with_replacement<I, RNG>(rng : &mut RNG, sample_size : usize, mut iter : I) -> Vec<I::Item>
+    where I : Iterator,
+          I::Item : Clone,
+          RNG : Rng
+{
+    if sample_size == 0 {
+        return Vec::new();
+    }
+    
+    let mut samples = match iter.next() {
+        Some(first) => vec![first; sample_size],
+        None => return Vec::new()
+    };
+    let mut position : usize = 1;
+    
+    let mut replacements = BinaryHeap::with_capacity(sample_size);
+    for slot in 0..sample_size {
+        replacements.push(Reverse((next_replacement(rng, position), slot)));
+    }
+    
+    while let Some(&Reverse((next, _))) = replacements.peek() {
+        let element = match iter.nth(next - position - 1) {
+            Some(element) => element,
+            None => break
+        };
+        position = next;
+        
+        while let Some(&Reverse((p, slot))) = replacements.peek() {
+            if p != position {
+                break;
+            }
+            replacements.pop();
+            samples[slot] = element.clone();
+            replacements.push(Reverse((next_replacement(rng, position), slot)));
         }
     }
+    
+    samples
+}
+
+// The position of the next item that will replace the item held by a
+// reservoir of one item, after it has seen `seen` items.  The next
+// replacement is after position m with probability seen/m.
+fn next_replacement<RNG : Rng>(rng : &mut RNG, seen : usize) -> usize {
+    let next = (seen as f64 / open01(rng)).floor();
+    if next >= usize::MAX as f64 {
+        usize::MAX
+    } else {
+        next as usize + 1
+    }
 }
 
+fn open01<RNG : Rng>(rng : &mut RNG) -> f64 {
+    let Open01(u) = rng.gen::<Open01<f64>>();
+    u
+}
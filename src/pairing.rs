@@ -0,0 +1,171 @@
+use rand::{Rng, ThreadRng};
+
+use super::replace;
+
+/// A reservoir that holds a uniform random sample of a population into
+/// which items are inserted and from which they are deleted.
+///
+/// Implements Gemulla, Lehner and Haas' random pairing.  While no
+/// deletions are outstanding, inserted items are sampled with
+/// Algorithm R, as `sample` does.  A deletion of a sampled item
+/// removes it from the sample, and each deletion is later
+/// "compensated" by pairing it with an insertion: an inserted item is
+/// added to the sample if it is paired with the deletion of a sampled
+/// item, and is not sampled otherwise.  That keeps the sample a
+/// uniform sample of the live items without rescanning the population.
+///
+/// Items are identified by equality, so items in the population should
+/// be distinct, or indistinguishable.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::RandomPairingReservoir;
+/// # fn main() {
+/// let mut reservoir = RandomPairingReservoir::new(3, thread_rng());
+///
+/// for sku in 0..10 {
+///     reservoir.insert(sku);
+/// }
+/// for sku in 0..5 {
+///     reservoir.delete(&sku);
+/// }
+///
+/// assert_eq!(5, reservoir.population());
+/// assert!(reservoir.samples().iter().all(|sku| *sku >= 5));
+/// # }
+/// ```
+///
+/// The sample remains uniform after interleaved insertions and
+/// deletions:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::RandomPairingReservoir;
+/// # fn main() {
+/// let mut counts = [0usize; 30];
+///
+/// for _ in 0..10000 {
+///     let mut reservoir = RandomPairingReservoir::new(3, thread_rng());
+///     for i in 0..20 {
+///         reservoir.insert(i);
+///         if i % 4 == 3 {
+///             reservoir.delete(&(i - 2));
+///             reservoir.delete(&(i - 3));
+///         }
+///     }
+///     for i in 20..30 {
+///         reservoir.insert(i);
+///     }
+///
+///     assert_eq!(3, reservoir.samples().len());
+///     for i in reservoir.samples() {
+///         counts[*i] += 1;
+///     }
+/// }
+///
+/// // Each of the 20 live items is expected to be sampled 1500 times
+/// for i in 0..30 {
+///     if i < 20 && i % 4 < 2 {
+///         assert_eq!(0, counts[i]);
+///     } else {
+///         assert!(counts[i] > 1250 && counts[i] < 1750);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RandomPairingReservoir<T, RNG = ThreadRng> {
+    sample_size : usize,
+    samples : Vec<T>,
+    population : usize,
+    uncompensated_sampled : usize,
+    uncompensated_unsampled : usize,
+    rng : RNG
+}
+
+impl<T, RNG> RandomPairingReservoir<T, RNG>
+    where T : PartialEq,
+          RNG : Rng
+{
+    /// Create an empty reservoir that will hold at most `sample_size`
+    /// items, chosen with random numbers from `rng`.
+    pub fn new(sample_size : usize, rng : RNG) -> Self {
+        RandomPairingReservoir {
+            sample_size,
+            samples: Vec::with_capacity(sample_size),
+            population: 0,
+            uncompensated_sampled: 0,
+            uncompensated_unsampled: 0,
+            rng
+        }
+    }
+    
+    /// Insert an item into the population, which may or may not add it
+    /// to the sample.
+    pub fn insert(&mut self, item : T) {
+        self.population += 1;
+        
+        let uncompensated = self.uncompensated_sampled + self.uncompensated_unsampled;
+        if uncompensated == 0 {
+            if self.samples.len() < self.sample_size {
+                self.samples.push(item);
+            } else {
+                replace(&mut self.samples, &mut self.rng, self.population, item);
+            }
+        } else if self.rng.gen_range(0, uncompensated) < self.uncompensated_sampled {
+            self.samples.push(item);
+            self.uncompensated_sampled -= 1;
+        } else {
+            self.uncompensated_unsampled -= 1;
+        }
+    }
+    
+    /// Delete an item from the population, removing it from the sample
+    /// if it was sampled.  Returns whether the item was sampled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the population is empty.
+    pub fn delete(&mut self, item : &T) -> bool {
+        assert!(self.population > 0, "cannot delete an item from an empty population");
+        self.population -= 1;
+        
+        match self.samples.iter().position(|s| s == item) {
+            Some(index) => {
+                self.samples.swap_remove(index);
+                self.uncompensated_sampled += 1;
+                true
+            }
+            None => {
+                self.uncompensated_unsampled += 1;
+                false
+            }
+        }
+    }
+    
+    /// The items sampled from the live population.
+    pub fn samples(&self) -> &[T] {
+        &self.samples
+    }
+    
+    /// The number of live items in the population.
+    pub fn population(&self) -> usize {
+        self.population
+    }
+    
+    /// The maximum number of items the reservoir will hold.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+    
+    /// Consume the reservoir, returning the items sampled.
+    pub fn into_samples(self) -> Vec<T> {
+        self.samples
+    }
+}
@@ -0,0 +1,88 @@
+use rand::thread_rng;
+use rayon::iter::ParallelIterator;
+
+use super::Summary;
+
+/// Return a random sample of a known maximum size from a parallel
+/// iterator, using all available cores.
+///
+/// Each split of the iterator is sampled with Algorithm R, as `sample`
+/// does, and the samples of the splits are combined with
+/// `Summary::merge`, so every item is as likely to be sampled as it
+/// would be by `sample`.  Random numbers are drawn from `thread_rng()`
+/// of each of rayon's worker threads.
+///
+/// Requires the `rayon` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rayon;
+/// # extern crate reservoir;
+/// # use rayon::prelude::*;
+/// # use reservoir::par_sample;
+/// # fn main() {
+/// let data : Vec<u32> = (0..100000).collect();
+///
+/// let samples = par_sample(4, data.par_iter().filter(|n| *n % 2 == 0));
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.iter().all(|n| *n % 2 == 0));
+/// # }
+/// ```
+///
+/// Every item is equally likely to be sampled:
+///
+/// ```
+/// # extern crate rayon;
+/// # extern crate reservoir;
+/// # use rayon::prelude::*;
+/// # use reservoir::par_sample;
+/// # fn main() {
+/// let mut counts = [0usize; 10];
+///
+/// for _ in 0..10000 {
+///     for e in par_sample(3, (0..10usize).into_par_iter()) {
+///         counts[e] += 1;
+///     }
+/// }
+///
+/// // Each element is expected to be sampled 3000 times
+/// assert!(counts.iter().all(|&c| c > 2600 && c < 3400));
+/// # }
+/// ```
+pub fn par_sample<I>(sample_size : usize, iter : I) -> Vec<I::Item>
+    where I : ParallelIterator
+{
+    par_sample_summary(sample_size, iter).into_samples()
+}
+
+/// Return a summary of a parallel iterator, holding a random sample of
+/// a known maximum size and the number of items in the iterator.
+///
+/// Requires the `rayon` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rayon;
+/// # extern crate reservoir;
+/// # use rayon::prelude::*;
+/// # use reservoir::par_sample_summary;
+/// # fn main() {
+/// let summary = par_sample_summary(10, (0..1000).into_par_iter());
+///
+/// assert_eq!(1000, summary.seen());
+/// assert_eq!(10, summary.samples().len());
+/// # }
+/// ```
+pub fn par_sample_summary<I>(sample_size : usize, iter : I) -> Summary<I::Item>
+    where I : ParallelIterator
+{
+    iter
+        .fold(|| Summary::new(sample_size), |mut summary, item| {
+            summary.push(&mut thread_rng(), item);
+            summary
+        })
+        .reduce(|| Summary::new(sample_size), |a, b| a.merge(b, &mut thread_rng()))
+}
@@ -0,0 +1,190 @@
+use rand::{Rng, ThreadRng};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use super::{Snapshot, Summary};
+
+/// A reservoir that is fed one item at a time, for when items are
+/// pushed by callbacks instead of being pulled from an iterator.
+///
+/// Collects the same random sample that `sample` would from the
+/// sequence of pushed items, using Algorithm R.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::Reservoir;
+/// # fn main() {
+/// let mut reservoir = Reservoir::new(4, thread_rng());
+///
+/// for i in 0..10 {
+///     reservoir.push(i);
+/// }
+///
+/// assert_eq!(10, reservoir.seen());
+/// assert_eq!(4, reservoir.samples().len());
+/// assert!(reservoir.samples().iter().all(|e| *e >= 0 && *e < 10));
+/// # }
+/// ```
+///
+/// Until more items have been pushed than the sample size, the
+/// reservoir holds all of them:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::Reservoir;
+/// # fn main() {
+/// let mut reservoir = Reservoir::new(20, thread_rng());
+///
+/// reservoir.extend(0..10);
+///
+/// let expected_samples : Vec<i32> = (0..10).collect();
+/// assert_eq!(expected_samples, reservoir.into_samples());
+/// # }
+/// ```
+///
+/// With the `serde` feature, a reservoir can be serialized and
+/// deserialized along with the state of its random number generator,
+/// if the generator supports serde.  Otherwise, serialize its
+/// `summary` and resume sampling with `Reservoir::from_summary`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reservoir<T, RNG = ThreadRng> {
+    summary : Summary<T>,
+    rng : RNG
+}
+
+impl<T, RNG> Reservoir<T, RNG>
+    where RNG : Rng
+{
+    /// Create an empty reservoir that will hold at most `sample_size`
+    /// items, chosen with random numbers from `rng`.
+    pub fn new(sample_size : usize, rng : RNG) -> Self {
+        Reservoir::from_summary(Summary::new(sample_size), rng)
+    }
+    
+    /// Create a reservoir that continues sampling the stream described
+    /// by `summary`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate rand;
+    /// # extern crate reservoir;
+    /// # use rand::thread_rng;
+    /// # use reservoir::{Reservoir, sample_summary};
+    /// # fn main() {
+    /// let left = sample_summary(&mut thread_rng(), 4, 0..10);
+    /// let right = sample_summary(&mut thread_rng(), 4, 10..20);
+    ///
+    /// let mut reservoir = Reservoir::from_summary(left.merge(right, &mut thread_rng()), thread_rng());
+    /// reservoir.extend(20..30);
+    ///
+    /// assert_eq!(30, reservoir.seen());
+    /// assert_eq!(4, reservoir.samples().len());
+    /// # }
+    /// ```
+    pub fn from_summary(summary : Summary<T>, rng : RNG) -> Self {
+        Reservoir {
+            summary,
+            rng
+        }
+    }
+    
+    /// Offer an item to the reservoir, which may or may not keep it
+    /// in the sample.
+    pub fn push(&mut self, item : T) {
+        self.summary.push(&mut self.rng, item);
+    }
+    
+    /// The items sampled so far.
+    pub fn samples(&self) -> &[T] {
+        self.summary.samples()
+    }
+    
+    /// The number of items that have been pushed into the reservoir.
+    pub fn seen(&self) -> usize {
+        self.summary.seen()
+    }
+    
+    /// The maximum number of items the reservoir will hold.
+    pub fn sample_size(&self) -> usize {
+        self.summary.sample_size()
+    }
+    
+    /// A summary of the items pushed so far.
+    pub fn summary(&self) -> &Summary<T> {
+        &self.summary
+    }
+    
+    /// A snapshot of the numbers calculated from the items sampled so
+    /// far by `value`, for quantiles and other statistics of the items
+    /// pushed.
+    ///
+    /// Values that do not convert to `f64` with `snapshot`, such as
+    /// `u64` nanoseconds, can be converted with `as`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate rand;
+    /// # extern crate reservoir;
+    /// # use rand::thread_rng;
+    /// # use reservoir::Reservoir;
+    /// # fn main() {
+    /// let mut latencies = Reservoir::new(100, thread_rng());
+    /// latencies.extend(vec![1200u64, 800, 1500, 900]);
+    ///
+    /// let snapshot = latencies.snapshot_by(|&nanos| nanos as f64);
+    ///
+    /// assert_eq!(Some(800.0), snapshot.min());
+    /// assert_eq!(Some(1050.0), snapshot.median());
+    /// # }
+    /// ```
+    pub fn snapshot_by<F>(&self, value : F) -> Snapshot
+        where F : FnMut(&T) -> f64
+    {
+        self.summary.snapshot_by(value)
+    }
+    
+    /// Consume the reservoir, returning the items sampled.
+    pub fn into_samples(self) -> Vec<T> {
+        self.summary.into_samples()
+    }
+    
+    /// Consume the reservoir, returning a summary of the items pushed.
+    pub fn into_summary(self) -> Summary<T> {
+        self.summary
+    }
+}
+
+impl<T, RNG> Reservoir<T, RNG>
+    where T : Copy + Into<f64>,
+          RNG : Rng
+{
+    /// A snapshot of the values sampled so far, for quantiles and other
+    /// statistics of the values pushed.
+    pub fn snapshot(&self) -> Snapshot {
+        self.summary.snapshot()
+    }
+}
+
+// A reservoir never pins its items, so it can be moved while pinned
+// even if they cannot, and fed through `Pin<&mut Reservoir>` as a
+// `Sink`.
+impl<T, RNG : Unpin> Unpin for Reservoir<T, RNG> {}
+
+impl<T, RNG> Extend<T> for Reservoir<T, RNG>
+    where RNG : Rng
+{
+    fn extend<I : IntoIterator<Item=T>>(&mut self, iter : I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
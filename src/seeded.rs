@@ -0,0 +1,175 @@
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// A small, fast random number generator that produces the same
+/// sequence of numbers for the same seed on every platform.
+///
+/// Implements Sebastiano Vigna's SplitMix64.  It is not suitable for
+/// cryptography.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::Rng;
+/// # use reservoir::SeededRng;
+/// # fn main() {
+/// let mut rng = SeededRng::new(42);
+///
+/// assert_eq!(13679457532755275413, rng.next_u64());
+/// assert_eq!(2949826092126892291, rng.next_u64());
+/// # }
+/// ```
+///
+/// With the `serde` feature, the generator can be serialized, and so
+/// can a `Reservoir` that uses it.  A reservoir restored from a
+/// checkpoint continues exactly as the original would have:
+///
+/// ```
+/// # extern crate reservoir;
+/// # extern crate serde_json;
+/// # use reservoir::{Reservoir, SeededRng};
+/// # #[cfg(not(feature = "serde"))] fn main() {}
+/// # #[cfg(feature = "serde")]
+/// # fn main() {
+/// let mut reservoir = Reservoir::new(4, SeededRng::new(42));
+/// reservoir.extend(0..50);
+///
+/// let checkpoint = serde_json::to_string(&reservoir).unwrap();
+/// reservoir.extend(50..100);
+///
+/// let mut resumed : Reservoir<i32, SeededRng> = serde_json::from_str(&checkpoint).unwrap();
+/// resumed.extend(50..100);
+///
+/// assert_eq!(reservoir.samples(), resumed.samples());
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeededRng {
+    state : u64
+}
+
+impl SeededRng {
+    /// Create a generator from a seed.
+    pub fn new(seed : u64) -> Self {
+        SeededRng {
+            state: seed
+        }
+    }
+    
+    /// Return a number uniformly distributed between 0 (inclusive) and
+    /// `n` (exclusive), by rejection sampling, so that the result does
+    /// not depend on the implementation of the `rand` crate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn below(&mut self, n : u64) -> u64 {
+        assert!(n > 0, "cannot draw a number below zero");
+        
+        let limit = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return x % n;
+            }
+        }
+    }
+}
+
+impl Rng for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Return a random sample of a known maximum size from an iterator of
+/// unknown length, that is always the same for the same seed and the
+/// same sequence of items.
+///
+/// The sample is guaranteed not to change between platforms or
+/// versions of this crate.  It is chosen by Algorithm R, as `sample`
+/// does, with random numbers from a `SeededRng` created from the
+/// seed: after the reservoir is full, the n'th item (counting from
+/// one) replaces the item at index `rng.below(n)` if that index is
+/// less than the sample size.
+///
+/// Other ways of sampling with a `SeededRng` are repeatable, but may
+/// change if this crate or the `rand` crate change how they draw
+/// random numbers.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::seeded_sample;
+/// # fn main() {
+/// assert_eq!(vec![65, 74, 72, 59], seeded_sample(42, 4, 0..100));
+/// assert_eq!(vec![65, 74, 72, 59], seeded_sample(42, 4, 0..100));
+/// assert_eq!(vec![49, 10, 63, 27], seeded_sample(7, 4, 0..100));
+///
+/// let words = "the quick brown fox jumps over the lazy dog".split(' ');
+/// assert_eq!(vec!["the", "fox", "brown"], seeded_sample(2023, 3, words));
+/// # }
+/// ```
+pub fn seeded_sample<I>(seed : u64, sample_size : usize, iter : I) -> Vec<I::Item>
+    where I : Iterator
+{
+    let mut samples = Vec::<I::Item>::with_capacity(sample_size);
+    seeded_sample_into(&mut samples, seed, sample_size, iter);
+    samples
+}
+
+/// Collect a random sample of a known maximum size from an iterator
+/// of unknown length into an existing Vec, that is always the same
+/// for the same seed and the same sequence of items.
+///
+/// Returns the number of items in the iterator.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::seeded_sample_into;
+/// # fn main() {
+/// let mut samples = vec![-1];
+///
+/// let count = seeded_sample_into(&mut samples, 42, 4, 0..100);
+///
+/// assert_eq!(100, count);
+/// assert_eq!(vec![-1, 65, 74, 72, 59], samples);
+/// # }
+/// ```
+pub fn seeded_sample_into<I>(samples : &mut Vec<I::Item>, seed : u64, sample_size : usize, iter : I) -> usize
+    where I : Iterator
+{
+    let mut rng = SeededRng::new(seed);
+    let original_length = samples.len();
+    let mut count : u64 = 0;
+    
+    for element in iter {
+        count += 1;
+        
+        if count <= sample_size as u64 {
+            samples.push(element);
+        } else {
+            let index = rng.below(count);
+            if index < sample_size as u64 {
+                samples[original_length + index as usize] = element;
+            }
+        }
+    }
+    
+    count as usize
+}
@@ -0,0 +1,293 @@
+use rand::Rng;
+
+use super::{open01, replace};
+
+/// Return a random sample of a known maximum size from an iterator of
+/// known length, in the order in which the items occur in the
+/// iterator.
+///
+/// Implements Vitter's Algorithm D, which calculates how many items to
+/// skip before each sampled item, and skips over them with
+/// `Iterator::nth`.  It draws O(k) random numbers to sample k items,
+/// and falls back to Vitter's simpler Algorithm A, which is faster
+/// when k is large relative to the number of items.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sequential_sample;
+/// # fn main() {
+/// let samples = sequential_sample(&mut thread_rng(), 4, 0..1000000);
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.windows(2).all(|w| w[0] < w[1]));
+///
+/// let all : Vec<i32> = sequential_sample(&mut thread_rng(), 20, 0..10);
+/// assert_eq!((0..10).collect::<Vec<i32>>(), all);
+/// # }
+/// ```
+///
+/// Every item is equally likely to be sampled:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sequential_sample;
+/// # fn main() {
+/// let mut counts = [0usize; 100];
+///
+/// for _ in 0..10000 {
+///     for e in sequential_sample(&mut thread_rng(), 3, 0..100) {
+///         counts[e] += 1;
+///     }
+/// }
+///
+/// // Each element is expected to be sampled 300 times
+/// assert!(counts.iter().all(|&c| c > 220 && c < 380));
+/// # }
+/// ```
+///
+/// Including when the sample is large enough relative to the number
+/// of items for Algorithm A to take over:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sequential_sample;
+/// # fn main() {
+/// let mut counts = [0usize; 100];
+///
+/// for _ in 0..10000 {
+///     for e in sequential_sample(&mut thread_rng(), 30, 0..100) {
+///         counts[e] += 1;
+///     }
+/// }
+///
+/// // Each element is expected to be sampled 3000 times
+/// assert!(counts.iter().all(|&c| c > 2750 && c < 3250));
+/// # }
+/// ```
+pub fn sequential_sample<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Vec<I::Item>
+    where I : ExactSizeIterator,
+          RNG : Rng
+{
+    let mut samples = Vec::<I::Item>::with_capacity(sample_size.min(iter.len()));
+    sequential_sample_into(&mut samples, rng, sample_size, iter);
+    samples
+}
+
+/// Collect a random sample of a known maximum size from an iterator of
+/// known length into an existing Vec, in the order in which the items
+/// occur in the iterator, using Algorithm D.
+///
+/// Returns the number of items in the iterator.  If the iterator ends
+/// before the length it reported, this is only a lower bound.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sequential_sample_into;
+/// # fn main() {
+/// let mut samples : Vec<i32> = vec![99,100];
+///
+/// let count = sequential_sample_into(&mut samples, &mut thread_rng(), 4, 0..10);
+///
+/// assert_eq!(10, count);
+/// assert_eq!(6, samples.len());
+/// assert_eq!(&[99, 100], &samples[..2]);
+/// assert!(samples[2..].windows(2).all(|w| w[0] < w[1]));
+/// # }
+/// ```
+pub fn sequential_sample_into<I, RNG>(samples : &mut Vec<I::Item>, rng : &mut RNG, sample_size : usize, iter : I) -> usize
+    where I : ExactSizeIterator,
+          RNG : Rng
+{
+    let len = iter.len();
+    sample_of_length(samples, rng, sample_size, len, iter)
+}
+
+// Sample an iterator that should have `len` items with Algorithm D,
+// returning the number of items it turns out to have.  Its length
+// cannot be relied on, so stops early if the iterator has fewer items,
+// and carries on with Algorithm R if it has more.  If it stops early
+// in the middle of a skip, the count is a lower bound, since `nth`
+// does not say how many items it consumed.
+pub(crate) fn sample_of_length<I, RNG>(samples : &mut Vec<I::Item>, rng : &mut RNG, sample_size : usize, len : usize, mut iter : I) -> usize
+    where I : Iterator,
+          RNG : Rng
+{
+    let original_length = samples.len();
+    
+    if sample_size >= len {
+        samples.extend(iter.by_ref().take(len));
+        let taken = samples.len() - original_length;
+        if taken < len {
+            return taken;
+        }
+    } else {
+        let mut consumed = 0;
+        let mut skips = Skips::new(rng, sample_size, len);
+        while let Some(skip) = skips.next(rng) {
+            match iter.nth(skip) {
+                Some(item) => samples.push(item),
+                None => return consumed
+            }
+            consumed += skip + 1;
+        }
+        if consumed < len && iter.nth(len - consumed - 1).is_none() {
+            return consumed;
+        }
+    }
+    
+    let mut count = len;
+    for element in iter {
+        count += 1;
+        
+        if count <= sample_size {
+            samples.push(element);
+        } else {
+            replace(&mut samples[original_length..], rng, count, element);
+        }
+    }
+    count
+}
+
+// Use Algorithm A when the number of records left to select is more
+// than 1/13th of the records remaining, as Vitter recommends.
+const ALPHA_INV : f64 = 13.0;
+
+// Generates the number of records to skip before each record selected
+// by Vitter's Algorithm D, when selecting n of N records in order.
+// Switches to the simpler Algorithm A when n is large relative to N.
+pub(crate) struct Skips {
+    n : usize,
+    remaining : f64,
+    ninv : f64,
+    vprime : f64,
+    qu1 : f64,
+    threshold : f64,
+    method_a : bool
+}
+
+impl Skips {
+    // Requires n <= population
+    pub(crate) fn new<RNG : Rng>(rng : &mut RNG, n : usize, population : usize) -> Self {
+        let nreal = n as f64;
+        let ninv = 1.0 / nreal;
+        Skips {
+            n,
+            remaining: population as f64,
+            ninv,
+            vprime: (open01(rng).ln() * ninv).exp(),
+            qu1: population as f64 - nreal + 1.0,
+            threshold: ALPHA_INV * nreal,
+            method_a: false
+        }
+    }
+    
+    pub(crate) fn next<RNG : Rng>(&mut self, rng : &mut RNG) -> Option<usize> {
+        if self.n == 0 {
+            return None;
+        }
+        
+        if self.n > 1 && !self.method_a && self.threshold >= self.remaining {
+            self.method_a = true;
+        }
+        
+        let s = if self.n == 1 {
+            if self.method_a {
+                (self.remaining * rng.gen::<f64>()).floor()
+            } else {
+                (self.remaining * self.vprime).floor()
+            }
+        } else if self.method_a {
+            self.skip_a(rng)
+        } else {
+            self.skip_d(rng)
+        };
+        
+        let s = s.min(self.remaining - self.n as f64).max(0.0);
+        self.remaining -= s + 1.0;
+        self.n -= 1;
+        Some(s as usize)
+    }
+    
+    fn skip_a<RNG : Rng>(&mut self, rng : &mut RNG) -> f64 {
+        let v = rng.gen::<f64>();
+        let mut s = 0.0;
+        let mut top = self.remaining - self.n as f64;
+        let mut nreal = self.remaining;
+        let mut quot = top / nreal;
+        
+        while quot > v {
+            s += 1.0;
+            top -= 1.0;
+            nreal -= 1.0;
+            quot = quot * top / nreal;
+        }
+        s
+    }
+    
+    fn skip_d<RNG : Rng>(&mut self, rng : &mut RNG) -> f64 {
+        let n = self.n as f64;
+        let big_n = self.remaining;
+        let nmin1inv = 1.0 / (n - 1.0);
+        let mut s;
+        
+        loop {
+            // Step D2: generate U and X
+            let mut x;
+            loop {
+                x = big_n * (1.0 - self.vprime);
+                s = x.floor();
+                if s < self.qu1 {
+                    break;
+                }
+                self.vprime = (open01(rng).ln() * self.ninv).exp();
+            }
+            let u = open01(rng);
+            
+            // Step D3: accept?
+            let y1 = (u * big_n / self.qu1).ln() * nmin1inv;
+            let y1 = y1.exp();
+            self.vprime = y1 * (1.0 - x / big_n) * (self.qu1 / (self.qu1 - s));
+            if self.vprime <= 1.0 {
+                break;
+            }
+            
+            // Step D4: accept?
+            let mut y2 = 1.0;
+            let mut top = big_n - 1.0;
+            let (mut bottom, limit) = if n - 1.0 > s {
+                (big_n - n, big_n - s)
+            } else {
+                (big_n - s - 1.0, self.qu1)
+            };
+            let mut t = big_n - 1.0;
+            while t >= limit {
+                y2 = y2 * top / bottom;
+                top -= 1.0;
+                bottom -= 1.0;
+                t -= 1.0;
+            }
+            if big_n / (big_n - x) >= y1 * (y2.ln() * nmin1inv).exp() {
+                self.vprime = (open01(rng).ln() * nmin1inv).exp();
+                break;
+            }
+            self.vprime = (open01(rng).ln() * self.ninv).exp();
+        }
+        
+        self.ninv = nmin1inv;
+        self.qu1 -= s;
+        self.threshold -= ALPHA_INV;
+        s
+    }
+}
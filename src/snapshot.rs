@@ -0,0 +1,229 @@
+/// How `Snapshot::quantile_with` chooses a value when the quantile
+/// falls between two sampled values.
+///
+/// With n values in ascending order, quantile `q` falls at position
+/// `q * (n - 1)`, counting from zero.  When that position is not a
+/// whole number it lies between the values at the positions either
+/// side of it, the lower and the higher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Interpolate linearly between the lower and higher values.
+    Linear,
+    /// The lower value.
+    Lower,
+    /// The higher value.
+    Higher,
+    /// Whichever value's position is nearer, or the higher value if
+    /// they are equally near.
+    Nearest,
+    /// The mean of the lower and higher values.
+    Midpoint
+}
+
+/// Summary statistics of a sample of numeric values, such as a
+/// reservoir used as a latency histogram.
+///
+/// # Accuracy
+///
+/// Quantiles of a uniform sample of k values are approximations of
+/// the quantiles of the stream it was drawn from.  By the
+/// Dvoretzky–Kiefer–Wolfowitz inequality, with probability at least
+/// 1 - δ the fraction of the stream below every sampled quantile `q`
+/// is within ε = sqrt(ln(2/δ) / 2k) of `q`, whatever the distribution
+/// of the stream.  A reservoir of 1000 values therefore puts the
+/// median between the 45th and 55th percentiles of the stream with
+/// probability 0.95.  The error falls only with the square root of
+/// the sample size, and is largest in relative terms for the extreme
+/// quantiles: a `p99` of 10000 samples is only reliably above the
+/// 97th percentile with probability 0.95.  `rank_error` and
+/// `quantile_bounds` calculate these bounds for the snapshot's sample
+/// size.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate reservoir;
+/// # use reservoir::{Interpolation, Snapshot};
+/// # fn main() {
+/// let snapshot = Snapshot::new(vec![4.0, 1.0, 3.0, 2.0]);
+///
+/// assert_eq!(Some(1.0), snapshot.min());
+/// assert_eq!(Some(4.0), snapshot.max());
+/// assert_eq!(Some(2.5), snapshot.mean());
+/// assert_eq!(Some(2.5), snapshot.median());
+/// assert_eq!(Some(2.0), snapshot.quantile_with(0.5, Interpolation::Lower));
+/// assert_eq!(Some(3.0), snapshot.quantile_with(0.5, Interpolation::Higher));
+/// # }
+/// ```
+///
+/// Sampling a stream of latencies:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::Reservoir;
+/// # fn main() {
+/// let mut reservoir = Reservoir::new(10000, thread_rng());
+/// reservoir.extend((0..1000000).map(|i| i as f64));
+///
+/// let snapshot = reservoir.snapshot();
+/// let (low, high) = snapshot.quantile_bounds(0.99, 0.999999).unwrap();
+///
+/// assert!(low <= 990000.0 && 990000.0 <= high);
+/// assert!(snapshot.rank_error(0.999999) < 0.03);
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    values : Vec<f64>
+}
+
+impl Snapshot {
+    /// Create a snapshot of the values.
+    ///
+    /// The values are ordered by `f64::total_cmp`, so any NaN values
+    /// sort above infinity, or below negative infinity if their sign
+    /// bit is set.
+    pub fn new<I>(values : I) -> Self
+        where I : IntoIterator,
+              I::Item : Into<f64>
+    {
+        let mut values : Vec<f64> = values.into_iter().map(Into::into).collect();
+        values.sort_by(f64::total_cmp);
+        
+        Snapshot {
+            values
+        }
+    }
+    
+    /// The values, in ascending order.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+    
+    /// The number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    
+    /// Whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    
+    /// The smallest value.
+    pub fn min(&self) -> Option<f64> {
+        self.values.first().cloned()
+    }
+    
+    /// The largest value.
+    pub fn max(&self) -> Option<f64> {
+        self.values.last().cloned()
+    }
+    
+    /// The mean of the values.
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.values.iter().sum::<f64>() / self.len() as f64)
+    }
+    
+    /// The standard deviation of the values.
+    pub fn stddev(&self) -> Option<f64> {
+        self.mean().map(|mean| {
+            let variance = self.values.iter()
+                .map(|v| (v - mean) * (v - mean))
+                .sum::<f64>() / self.len() as f64;
+            variance.sqrt()
+        })
+    }
+    
+    /// The value at quantile `q`, interpolating linearly between
+    /// values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not between 0 and 1.
+    pub fn quantile(&self, q : f64) -> Option<f64> {
+        self.quantile_with(q, Interpolation::Linear)
+    }
+    
+    /// The value at quantile `q`, with the given interpolation between
+    /// values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not between 0 and 1.
+    pub fn quantile_with(&self, q : f64, interpolation : Interpolation) -> Option<f64> {
+        assert!((0.0..=1.0).contains(&q), "quantile {} is not between 0 and 1", q);
+        
+        if self.is_empty() {
+            return None;
+        }
+        let position = q * (self.len() - 1) as f64;
+        let lower = self.values[position.floor() as usize];
+        let higher = self.values[position.ceil() as usize];
+        let fraction = position - position.floor();
+        
+        Some(match interpolation {
+            Interpolation::Linear => lower + fraction * (higher - lower),
+            Interpolation::Lower => lower,
+            Interpolation::Higher => higher,
+            Interpolation::Nearest => if fraction < 0.5 { lower } else { higher },
+            Interpolation::Midpoint => (lower + higher) / 2.0
+        })
+    }
+    
+    /// The median of the values.
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+    
+    /// The 99th percentile of the values.
+    pub fn p99(&self) -> Option<f64> {
+        self.quantile(0.99)
+    }
+    
+    /// The largest error, as a fraction of the stream, in the rank of
+    /// any quantile of the snapshot, with probability at least
+    /// `confidence`, if the values are a uniform random sample of the
+    /// stream.
+    ///
+    /// Infinite if the snapshot is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `confidence` is not strictly between 0 and 1.
+    pub fn rank_error(&self, confidence : f64) -> f64 {
+        assert!(confidence > 0.0 && confidence < 1.0, "confidence {} is not between 0 and 1", confidence);
+        
+        ((2.0 / (1.0 - confidence)).ln() / (2.0 * self.len() as f64)).sqrt()
+    }
+    
+    /// A range of sampled values containing quantile `q` of the stream
+    /// with probability at least `confidence`: the sampled quantiles
+    /// `rank_error` either side of `q`.
+    ///
+    /// When the sample is too small for the bound to lie within the
+    /// sampled values, the range extends to the smallest or largest
+    /// value sampled, which need not be the smallest or largest in the
+    /// stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not between 0 and 1, or `confidence` is not
+    /// strictly between 0 and 1.
+    pub fn quantile_bounds(&self, q : f64, confidence : f64) -> Option<(f64, f64)> {
+        assert!((0.0..=1.0).contains(&q), "quantile {} is not between 0 and 1", q);
+        
+        if self.is_empty() {
+            return None;
+        }
+        let error = self.rank_error(confidence);
+        let low = self.quantile_with((q - error).max(0.0), Interpolation::Lower)?;
+        let high = self.quantile_with((q + error).min(1.0), Interpolation::Higher)?;
+        Some((low, high))
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::{Rng, ThreadRng};
+
+use super::Summary;
+
+/// A reservoir that samples each stratum of a stream separately, where
+/// the stratum of an item is identified by a key calculated by the
+/// `key` function.
+///
+/// Holds a sample of up to `sample_size` items per stratum, chosen
+/// with Algorithm R.  The number of strata can be capped, in which
+/// case items of strata first seen after the cap has been reached are
+/// counted but not sampled.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::StratifiedReservoir;
+/// # fn main() {
+/// let mut reservoir = StratifiedReservoir::new(2, |&(customer, _)| customer, thread_rng());
+///
+/// reservoir.extend(vec![("alice", 1), ("bob", 2), ("alice", 3), ("alice", 4), ("carol", 5)]);
+///
+/// assert_eq!(3, reservoir.strata());
+/// for (customer, samples) in reservoir.iter() {
+///     assert!(samples.len() <= 2);
+///     assert!(samples.iter().all(|&(c, _)| c == *customer));
+/// }
+///
+/// let samples = reservoir.into_samples();
+/// assert_eq!(vec![("bob", 2)], samples["bob"]);
+/// # }
+/// ```
+///
+/// Capping the number of strata:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::StratifiedReservoir;
+/// # fn main() {
+/// let mut reservoir = StratifiedReservoir::with_max_strata(2, 10, |n : &u32| n % 100, thread_rng());
+///
+/// reservoir.extend(0..1000);
+///
+/// assert_eq!(10, reservoir.strata());
+/// assert_eq!(900, reservoir.dropped());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct StratifiedReservoir<K, T, F, RNG = ThreadRng> {
+    sample_size : usize,
+    max_strata : Option<usize>,
+    key : F,
+    strata : HashMap<K, Summary<T>>,
+    dropped : usize,
+    rng : RNG
+}
+
+impl<K, T, F, RNG> StratifiedReservoir<K, T, F, RNG>
+    where K : Eq + Hash,
+          F : FnMut(&T) -> K,
+          RNG : Rng
+{
+    /// Create an empty reservoir that will hold at most `sample_size`
+    /// items of each stratum, chosen with random numbers from `rng`.
+    pub fn new(sample_size : usize, key : F, rng : RNG) -> Self {
+        StratifiedReservoir {
+            sample_size,
+            max_strata: None,
+            key,
+            strata: HashMap::new(),
+            dropped: 0,
+            rng
+        }
+    }
+    
+    /// Create an empty reservoir that will hold at most `sample_size`
+    /// items of each of at most `max_strata` strata, chosen with random
+    /// numbers from `rng`.
+    pub fn with_max_strata(sample_size : usize, max_strata : usize, key : F, rng : RNG) -> Self {
+        StratifiedReservoir {
+            max_strata: Some(max_strata),
+            ..StratifiedReservoir::new(sample_size, key, rng)
+        }
+    }
+    
+    /// Offer an item to the reservoir of its stratum.
+    pub fn push(&mut self, item : T) {
+        let key = (self.key)(&item);
+        
+        if let Some(summary) = self.strata.get_mut(&key) {
+            summary.push(&mut self.rng, item);
+            return;
+        }
+        
+        if self.max_strata.is_some_and(|max| self.strata.len() >= max) {
+            self.dropped += 1;
+        } else {
+            let mut summary = Summary::new(self.sample_size);
+            summary.push(&mut self.rng, item);
+            self.strata.insert(key, summary);
+        }
+    }
+    
+    /// The number of strata being sampled.
+    pub fn strata(&self) -> usize {
+        self.strata.len()
+    }
+    
+    /// The number of items that were not sampled because they belonged
+    /// to new strata after the maximum number of strata was reached.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+    
+    /// The items sampled so far from the stratum identified by `key`.
+    pub fn samples(&self, key : &K) -> Option<&[T]> {
+        self.strata.get(key).map(|summary| summary.samples())
+    }
+    
+    /// Iterate over the strata and the items sampled from them, in
+    /// arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item=(&K, &[T])> {
+        self.strata.iter().map(|(key, summary)| (key, summary.samples()))
+    }
+    
+    /// Consume the reservoir, returning the items sampled from each
+    /// stratum.
+    pub fn into_samples(self) -> HashMap<K, Vec<T>> {
+        self.strata.into_iter().map(|(key, summary)| (key, summary.into_samples())).collect()
+    }
+    
+    /// Consume the reservoir, returning summaries of each stratum.
+    pub fn into_summaries(self) -> HashMap<K, Summary<T>> {
+        self.strata
+    }
+}
+
+impl<K, T, F, RNG> Extend<T> for StratifiedReservoir<K, T, F, RNG>
+    where K : Eq + Hash,
+          F : FnMut(&T) -> K,
+          RNG : Rng
+{
+    fn extend<I : IntoIterator<Item=T>>(&mut self, iter : I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+/// Return a random sample of at most `sample_size` items of each
+/// stratum of an iterator, where the stratum of an item is identified
+/// by a key calculated by the `key` function.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::stratified_sample;
+/// # fn main() {
+/// let samples = stratified_sample(&mut thread_rng(), 3, 0..100, |n| n % 4);
+///
+/// assert_eq!(4, samples.len());
+/// for (remainder, samples) in samples {
+///     assert_eq!(3, samples.len());
+///     assert!(samples.iter().all(|n| n % 4 == remainder));
+/// }
+/// # }
+/// ```
+pub fn stratified_sample<I, K, F, RNG>(rng : &mut RNG, sample_size : usize, iter : I, mut key : F) -> HashMap<K, Vec<I::Item>>
+    where I : Iterator,
+          K : Eq + Hash,
+          F : FnMut(&I::Item) -> K,
+          RNG : Rng
+{
+    let mut strata = HashMap::<K, Summary<I::Item>>::new();
+    for item in iter {
+        strata.entry(key(&item))
+            .or_insert_with(|| Summary::new(sample_size))
+            .push(rng, item);
+    }
+    strata.into_iter().map(|(key, summary)| (key, summary.into_samples())).collect()
+}
@@ -0,0 +1,153 @@
+use std::convert::Infallible;
+use std::pin::Pin;
+
+use futures::future::{self, Future, FutureExt};
+use futures::sink::Sink;
+use futures::stream::{Stream, StreamExt};
+use futures::task::{Context, Poll};
+use rand::Rng;
+
+use super::{Reservoir, replace};
+
+/// Return a future that resolves to a random sample of a known maximum
+/// size from an asynchronous stream of unknown length.
+///
+/// Requires the `futures` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use futures::executor::block_on;
+/// # use futures::stream;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_stream;
+/// # fn main() {
+/// let mut rng = thread_rng();
+///
+/// let samples = block_on(sample_stream(&mut rng, 4, stream::iter(0..10)));
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.iter().all(|e| *e >= 0 && *e < 10));
+/// # }
+/// ```
+pub fn sample_stream<'a, S, RNG>(rng : &'a mut RNG, sample_size : usize, stream : S) -> impl Future<Output=Vec<S::Item>> + 'a
+    where S : Stream + 'a,
+          RNG : Rng
+{
+    stream
+        .fold(Reservoir::new(sample_size, rng), |mut reservoir, item| {
+            reservoir.push(item);
+            future::ready(reservoir)
+        })
+        .map(Reservoir::into_samples)
+}
+
+/// Return a future that collects a random sample of a known maximum
+/// size from an asynchronous stream of unknown length into an
+/// existing Vec.  The future's output is the number of items in the
+/// stream.
+///
+/// Requires the `futures` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use futures::executor::block_on;
+/// # use futures::stream;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_stream_into;
+/// # fn main() {
+/// let mut samples : Vec<i32> = vec![99,100];
+///
+/// let count = block_on(sample_stream_into(&mut samples, &mut thread_rng(), 4, stream::iter(0..10)));
+///
+/// assert_eq!(10, count);
+/// assert_eq!(6, samples.len());
+/// assert_eq!(99, samples[0]);
+/// assert_eq!(100, samples[1]);
+/// assert!(samples[2..].iter().all(|e| *e >= 0 && *e < 10));
+/// # }
+/// ```
+pub fn sample_stream_into<'a, S, RNG>(samples : &'a mut Vec<S::Item>, rng : &'a mut RNG, sample_size : usize, stream : S) -> impl Future<Output=usize> + 'a
+    where S : Stream + 'a,
+          RNG : Rng
+{
+    let original_length = samples.len();
+    
+    stream
+        .fold((samples, rng, 0), move |(samples, rng, count), element| {
+            let count = count + 1;
+            
+            if count <= sample_size {
+                samples.push(element);
+            } else {
+                replace(&mut samples[original_length..], rng, count, element);
+            }
+            future::ready((samples, rng, count))
+        })
+        .map(|(_, _, count)| count)
+}
+
+/// A reservoir is a sink that is always ready to accept items, so that
+/// it can be fed by asynchronous tasks.
+///
+/// Requires the `futures` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate futures;
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use futures::channel::mpsc;
+/// # use futures::executor::LocalPool;
+/// # use futures::future::FutureExt;
+/// # use futures::stream::{self, StreamExt};
+/// # use futures::task::LocalSpawnExt;
+/// # use rand::thread_rng;
+/// # use reservoir::Reservoir;
+/// # fn main() {
+/// let mut pool = LocalPool::new();
+/// let (sender, receiver) = mpsc::unbounded();
+///
+/// for task in 0..4 {
+///     let events = stream::iter(task * 10 .. task * 10 + 10).map(Ok);
+///     pool.spawner().spawn_local(events.forward(sender.clone()).map(|_| ())).unwrap();
+/// }
+/// drop(sender);
+///
+/// let mut reservoir = Reservoir::new(5, thread_rng());
+/// pool.run_until(receiver.map(Ok).forward(&mut reservoir)).unwrap();
+///
+/// assert_eq!(40, reservoir.seen());
+/// assert_eq!(5, reservoir.samples().len());
+/// # }
+/// ```
+impl<T, RNG> Sink<T> for Reservoir<T, RNG>
+    where RNG : Rng + Unpin
+{
+    type Error = Infallible;
+    
+    fn poll_ready(self : Pin<&mut Self>, _cx : &mut Context) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+    
+    fn start_send(self : Pin<&mut Self>, item : T) -> Result<(), Infallible> {
+        self.get_mut().push(item);
+        Ok(())
+    }
+    
+    fn poll_flush(self : Pin<&mut Self>, _cx : &mut Context) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+    
+    fn poll_close(self : Pin<&mut Self>, _cx : &mut Context) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,294 @@
+use std::cmp::min;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use super::replace;
+use estimate::Estimator;
+use snapshot::Snapshot;
+
+/// A random sample of a stream, together with the number of items in
+/// the stream it was drawn from.
+///
+/// Summaries of separate streams can be merged into a summary of the
+/// concatenated streams, so that partitions of a data set can be
+/// sampled independently, in parallel, and then combined.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::sample_summary;
+/// # fn main() {
+/// let summary = sample_summary(&mut thread_rng(), 4, 0..10);
+///
+/// assert_eq!(10, summary.seen());
+/// assert_eq!(4, summary.samples().len());
+/// # }
+/// ```
+///
+/// With the `serde` feature, summaries can be serialized and
+/// deserialized, so that a long-running job can checkpoint the state
+/// of its sampling and resume it after a restart with
+/// `Reservoir::from_summary`.
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # extern crate serde_json;
+/// # use rand::thread_rng;
+/// # use reservoir::{Reservoir, Summary};
+/// # #[cfg(not(feature = "serde"))] fn main() {}
+/// # #[cfg(feature = "serde")]
+/// # fn main() {
+/// let mut reservoir = Reservoir::new(4, thread_rng());
+/// reservoir.extend(0..10);
+///
+/// let checkpoint = serde_json::to_string(reservoir.summary()).unwrap();
+///
+/// let summary : Summary<i32> = serde_json::from_str(&checkpoint).unwrap();
+/// let mut resumed = Reservoir::from_summary(summary, thread_rng());
+/// resumed.extend(10..20);
+///
+/// assert_eq!(20, resumed.seen());
+/// assert_eq!(4, resumed.samples().len());
+/// # }
+/// ```
+///
+/// A checkpoint that does not hold as many samples as its sample size
+/// and count of items seen imply is rejected:
+///
+/// ```
+/// # extern crate reservoir;
+/// # extern crate serde_json;
+/// # use reservoir::Summary;
+/// # #[cfg(not(feature = "serde"))] fn main() {}
+/// # #[cfg(feature = "serde")]
+/// # fn main() {
+/// let corrupt = r#"{"sample_size":4,"samples":[1,2],"seen":10}"#;
+///
+/// assert!(serde_json::from_str::<Summary<i32>>(corrupt).is_err());
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SummaryState<T>"))]
+pub struct Summary<T> {
+    sample_size : usize,
+    samples : Vec<T>,
+    seen : usize
+}
+
+// The fields of a deserialized summary, before they have been checked
+// to be consistent.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct SummaryState<T> {
+    sample_size : usize,
+    samples : Vec<T>,
+    seen : usize
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<SummaryState<T>> for Summary<T> {
+    type Error = String;
+    
+    fn try_from(state : SummaryState<T>) -> Result<Self, String> {
+        let expected = min(state.seen, state.sample_size);
+        if state.samples.len() != expected {
+            return Err(format!("summary of {} items with sample size {} holds {} samples instead of {}",
+                               state.seen, state.sample_size, state.samples.len(), expected));
+        }
+        
+        Ok(Summary {
+            sample_size: state.sample_size,
+            samples: state.samples,
+            seen: state.seen
+        })
+    }
+}
+
+impl<T> Summary<T> {
+    /// Create a summary of an empty stream that will hold at most
+    /// `sample_size` items.
+    pub fn new(sample_size : usize) -> Self {
+        Summary {
+            sample_size,
+            samples: Vec::with_capacity(sample_size),
+            seen: 0
+        }
+    }
+    
+    /// The items sampled from the stream.
+    pub fn samples(&self) -> &[T] {
+        &self.samples
+    }
+    
+    /// The number of items in the stream.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+    
+    /// The maximum number of items the summary will hold.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+    
+    /// Consume the summary, returning the items sampled.
+    pub fn into_samples(self) -> Vec<T> {
+        self.samples
+    }
+    
+    /// Estimate totals, means and proportions over the whole stream
+    /// from the sample, or return `None` if nothing was sampled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate rand;
+    /// # extern crate reservoir;
+    /// # use rand::thread_rng;
+    /// # use reservoir::sample_summary;
+    /// # fn main() {
+    /// let summary = sample_summary(&mut thread_rng(), 10, 0..10);
+    /// let mean = summary.estimator().unwrap().mean(|&x| x as f64);
+    ///
+    /// assert_eq!(4.5, mean.value());
+    /// assert_eq!(0.0, mean.variance());
+    ///
+    /// let empty = sample_summary(&mut thread_rng(), 10, 0..0);
+    /// assert!(empty.estimator().is_none());
+    /// # }
+    /// ```
+    pub fn estimator(&self) -> Option<Estimator<'_, T>> {
+        Estimator::new(&self.samples, self.seen)
+    }
+    
+    /// A snapshot of the numbers calculated from the sampled items by
+    /// `value`, for quantiles and other statistics of the stream.
+    pub fn snapshot_by<F>(&self, value : F) -> Snapshot
+        where F : FnMut(&T) -> f64
+    {
+        Snapshot::new(self.samples.iter().map(value))
+    }
+    
+    /// Merge two summaries into a summary of the concatenation of their
+    /// streams.
+    ///
+    /// The merged summary holds a uniform random sample of the items
+    /// in both streams.  Its sample size is the smaller of the two
+    /// summaries' sample sizes.  The number of samples taken from each
+    /// summary follows the hypergeometric distribution of the number
+    /// of items that a uniform sample of the combined stream would
+    /// include from each stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate rand;
+    /// # extern crate reservoir;
+    /// # use rand::thread_rng;
+    /// # use reservoir::sample_summary;
+    /// # fn main() {
+    /// let mut counts = [0usize; 10];
+    ///
+    /// for _ in 0..10000 {
+    ///     let left = sample_summary(&mut thread_rng(), 4, 0..3);
+    ///     let right = sample_summary(&mut thread_rng(), 4, 3..10);
+    ///
+    ///     let merged = left.merge(right, &mut thread_rng());
+    ///
+    ///     assert_eq!(10, merged.seen());
+    ///     assert_eq!(4, merged.samples().len());
+    ///     for e in merged.samples() {
+    ///         counts[*e] += 1;
+    ///     }
+    /// }
+    ///
+    /// // Each element is expected to be sampled 4000 times
+    /// assert!(counts.iter().all(|&c| c > 3600 && c < 4400));
+    /// # }
+    /// ```
+    pub fn merge<RNG>(self, other : Summary<T>, rng : &mut RNG) -> Summary<T>
+        where RNG : Rng
+    {
+        let sample_size = min(self.sample_size, other.sample_size);
+        let seen = self.seen + other.seen;
+        let merged_size = min(sample_size, seen);
+        
+        let mut remaining_self = self.seen;
+        let mut remaining_other = other.seen;
+        let mut from_self = 0;
+        for _ in 0..merged_size {
+            if rng.gen_range(0, remaining_self + remaining_other) < remaining_self {
+                from_self += 1;
+                remaining_self -= 1;
+            } else {
+                remaining_other -= 1;
+            }
+        }
+        
+        let mut samples = choose(self.samples, from_self, rng);
+        samples.extend(choose(other.samples, merged_size - from_self, rng));
+        
+        Summary {
+            sample_size,
+            samples,
+            seen
+        }
+    }
+    
+    pub(crate) fn push<RNG>(&mut self, rng : &mut RNG, item : T)
+        where RNG : Rng
+    {
+        self.seen += 1;
+        
+        if self.seen <= self.sample_size {
+            self.samples.push(item);
+        } else {
+            replace(&mut self.samples, rng, self.seen, item);
+        }
+    }
+}
+
+impl<T> Summary<T>
+    where T : Copy + Into<f64>
+{
+    /// A snapshot of the sampled values, for quantiles and other
+    /// statistics of the stream.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.samples.iter().cloned())
+    }
+}
+
+/// Return a summary of an iterator of unknown length, holding a random
+/// sample of a known maximum size and the number of items in the
+/// iterator.
+pub fn sample_summary<I, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Summary<I::Item>
+    where I : Iterator,
+          RNG : Rng
+{
+    let mut summary = Summary::new(sample_size);
+    for item in iter {
+        summary.push(rng, item);
+    }
+    summary
+}
+
+// Choose n of the items at random, by a partial Fisher-Yates shuffle.
+fn choose<T, RNG>(mut items : Vec<T>, n : usize, rng : &mut RNG) -> Vec<T>
+    where RNG : Rng
+{
+    let len = items.len();
+    for i in 0..n {
+        let j = rng.gen_range(i, len);
+        items.swap(i, j);
+    }
+    items.truncate(n);
+    items
+}
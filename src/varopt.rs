@@ -0,0 +1,247 @@
+use std::collections::BinaryHeap;
+
+use rand::{Rng, ThreadRng};
+
+use super::weighted::{Keyed, WeightError, validate};
+
+/// A reservoir of weighted items that minimises the variance of
+/// estimates of the total weight of arbitrary subsets of the items.
+///
+/// Implements the VarOpt_k scheme of Cohen, Duffield, Kaplan, Lund and
+/// Thorup.  The reservoir holds at most `sample_size` items.  Items
+/// heavier than a threshold, tau, are always held with their own
+/// weights.  Lighter items are sampled with probability proportional
+/// to their weights and held with the adjusted weight tau.  The sum of
+/// the adjusted weights of the items that satisfy a predicate is an
+/// unbiased, Horvitz-Thompson estimate of the total weight of all the
+/// items that satisfy it.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::VarOpt;
+/// # fn main() {
+/// let mut reservoir = VarOpt::new(3, thread_rng());
+///
+/// for &(bytes, port) in [(1500.0, 80), (40.0, 53), (900.0, 443), (60.0, 53), (7000.0, 443)].iter() {
+///     reservoir.insert(bytes, port).unwrap();
+/// }
+///
+/// assert_eq!(3, reservoir.len());
+/// // The heaviest flow is always sampled, with its own weight
+/// assert!(reservoir.samples().any(|(weight, port)| weight == 7000.0 && *port == 443));
+/// // The total weight is estimated exactly
+/// assert!((reservoir.total() - 9500.0).abs() < 1e-9);
+/// # }
+/// ```
+///
+/// Estimates of subsets are unbiased:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::VarOpt;
+/// # fn main() {
+/// let trials = 10000;
+/// let mut even_total = 0.0;
+///
+/// for _ in 0..trials {
+///     let mut reservoir = VarOpt::new(10, thread_rng());
+///     for n in 1..101 {
+///         reservoir.insert(n as f64, n).unwrap();
+///     }
+///     even_total += reservoir.estimate(|n| n % 2 == 0);
+/// }
+///
+/// // The total weight of the even items is 2550
+/// let mean = even_total / trials as f64;
+/// assert!(mean > 2500.0 && mean < 2600.0);
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VarOpt<T, RNG = ThreadRng> {
+    sample_size : usize,
+    large : BinaryHeap<Keyed<T>>,
+    small : Vec<T>,
+    threshold : f64,
+    rng : RNG
+}
+
+impl<T, RNG> VarOpt<T, RNG>
+    where RNG : Rng
+{
+    /// Create an empty reservoir that will hold at most `sample_size`
+    /// items, chosen with random numbers from `rng`.
+    pub fn new(sample_size : usize, rng : RNG) -> Self {
+        VarOpt {
+            sample_size,
+            large: BinaryHeap::with_capacity(sample_size + 1),
+            small: Vec::with_capacity(sample_size + 1),
+            threshold: 0.0,
+            rng
+        }
+    }
+    
+    /// Offer an item with a weight to the reservoir.
+    ///
+    /// Returns an error, and leaves the reservoir unchanged, if the
+    /// weight is not positive and finite.
+    pub fn insert(&mut self, weight : f64, item : T) -> Result<(), WeightError> {
+        let weight = validate(weight)?;
+        
+        if self.sample_size == 0 {
+            return Ok(());
+        }
+        if self.len() < self.sample_size {
+            self.large.push(Keyed { key: weight, item });
+            return Ok(());
+        }
+        
+        let mut moved = Vec::new();
+        let mut small_weight = self.threshold * self.small.len() as f64;
+        
+        if weight > self.threshold {
+            self.large.push(Keyed { key: weight, item });
+        } else {
+            moved.push(Keyed { key: weight, item });
+            small_weight += weight;
+        }
+        
+        while let Some(lightest) = self.large.peek().map(|k| k.key) {
+            if small_weight <= (self.small.len() + moved.len()) as f64 * lightest - lightest {
+                break;
+            }
+            small_weight += lightest;
+            moved.push(self.large.pop().unwrap());
+        }
+        
+        let threshold = small_weight / (self.small.len() + moved.len() - 1) as f64;
+        
+        let mut r = self.rng.gen::<f64>();
+        let mut dropped = None;
+        for (i, k) in moved.iter().enumerate() {
+            r -= 1.0 - k.key / threshold;
+            if r < 0.0 {
+                dropped = Some(i);
+                break;
+            }
+        }
+        match dropped {
+            Some(i) => {
+                moved.swap_remove(i);
+            }
+            // Only reachable through rounding error
+            None if self.small.is_empty() => {
+                moved.pop();
+            }
+            None => {
+                let i = self.rng.gen_range(0, self.small.len());
+                self.small.swap_remove(i);
+            }
+        }
+        
+        self.small.extend(moved.into_iter().map(|k| k.item));
+        self.threshold = threshold;
+        Ok(())
+    }
+    
+    /// The sampled items, each paired with its adjusted weight.
+    pub fn samples(&self) -> impl Iterator<Item=(f64, &T)> {
+        let threshold = self.threshold;
+        self.large.iter().map(|k| (k.key, &k.item))
+            .chain(self.small.iter().map(move |item| (threshold, item)))
+    }
+    
+    /// The threshold, tau, which is the adjusted weight of the items
+    /// that were sampled in proportion to their weights.  It is zero if
+    /// no items have been dropped from the reservoir.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+    
+    /// The number of items held by the reservoir.
+    pub fn len(&self) -> usize {
+        self.large.len() + self.small.len()
+    }
+    
+    /// Whether the reservoir holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// The maximum number of items the reservoir will hold.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+    
+    /// An unbiased estimate of the total weight of the items for which
+    /// `predicate` is true.
+    pub fn estimate<F>(&self, mut predicate : F) -> f64
+        where F : FnMut(&T) -> bool
+    {
+        self.samples()
+            .filter(|&(_, item)| predicate(item))
+            .map(|(weight, _)| weight)
+            .sum()
+    }
+    
+    /// The total weight of all the items, which is estimated exactly.
+    pub fn total(&self) -> f64 {
+        self.estimate(|_| true)
+    }
+    
+    /// Merge another reservoir into this one, so that it samples the
+    /// items of both streams.
+    ///
+    /// The other reservoir's items are inserted with their adjusted
+    /// weights, which keeps the estimates unbiased.  The merged
+    /// reservoir keeps this reservoir's sample size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate rand;
+    /// # extern crate reservoir;
+    /// # use rand::thread_rng;
+    /// # use reservoir::VarOpt;
+    /// # fn main() {
+    /// let mut left = VarOpt::new(5, thread_rng());
+    /// let mut right = VarOpt::new(5, thread_rng());
+    ///
+    /// for n in 1..51 {
+    ///     left.insert(n as f64, n).unwrap();
+    /// }
+    /// for n in 51..101 {
+    ///     right.insert(n as f64, n).unwrap();
+    /// }
+    ///
+    /// left.merge(right);
+    ///
+    /// assert_eq!(5, left.len());
+    /// assert!((left.total() - 5050.0).abs() < 1e-6);
+    /// # }
+    /// ```
+    pub fn merge<R>(&mut self, other : VarOpt<T, R>) {
+        let threshold = other.threshold;
+        
+        for k in other.large {
+            self.insert(k.key, k.item).expect("adjusted weights are valid");
+        }
+        for item in other.small {
+            self.insert(threshold, item).expect("adjusted weights are valid");
+        }
+    }
+    
+    /// Consume the reservoir, returning the sampled items, each paired
+    /// with its adjusted weight.
+    pub fn into_samples(self) -> Vec<(f64, T)> {
+        let threshold = self.threshold;
+        self.large.into_iter().map(|k| (k.key, k.item))
+            .chain(self.small.into_iter().map(|item| (threshold, item)))
+            .collect()
+    }
+}
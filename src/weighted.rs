@@ -0,0 +1,535 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::fmt;
+
+use rand::Rng;
+
+use super::open01;
+
+/// The reason an item's weight cannot be used for weighted sampling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeightError {
+    /// The weight was zero, so the item could never be sampled.
+    Zero,
+    /// The weight was less than zero.
+    Negative(f64),
+    /// The weight was infinite.
+    Infinite,
+    /// The weight was not a number.
+    NaN
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WeightError::Zero => write!(f, "weight is zero"),
+            WeightError::Negative(w) => write!(f, "weight {} is negative", w),
+            WeightError::Infinite => write!(f, "weight is infinite"),
+            WeightError::NaN => write!(f, "weight is not a number")
+        }
+    }
+}
+
+impl Error for WeightError {}
+
+pub(crate) fn validate(weight : f64) -> Result<f64, WeightError> {
+    if weight.is_nan() {
+        Err(WeightError::NaN)
+    } else if weight.is_infinite() {
+        Err(WeightError::Infinite)
+    } else if weight < 0.0 {
+        Err(WeightError::Negative(weight))
+    } else if weight == 0.0 {
+        Err(WeightError::Zero)
+    } else {
+        Ok(weight)
+    }
+}
+
+
+/// Return a random sample of a known maximum size from an iterator of
+/// `(weight, item)` pairs, where the probability of sampling an item
+/// is proportional to its weight.
+///
+/// Implements Efraimidis and Spirakis' Algorithm A-Res, which gives
+/// each item a random key derived from its weight and keeps the items
+/// with the largest keys.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::weighted_sample;
+/// # fn main() {
+/// let iter = (0..10).map(|i| (i as f64 + 1.0, i));
+///
+/// let samples = weighted_sample(&mut thread_rng(), 4, iter).unwrap();
+///
+/// assert_eq!(4, samples.len());
+/// assert!(samples.iter().all(|e| *e >= 0 && *e < 10));
+/// # }
+/// ```
+///
+/// Items are sampled in proportion to their weight:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::weighted_sample;
+/// # fn main() {
+/// let mut heavy_count = 0;
+///
+/// for _ in 0..10000 {
+///     let samples = weighted_sample(&mut thread_rng(), 1, vec![(1.0, "light"), (9.0, "heavy")].into_iter()).unwrap();
+///     if samples[0] == "heavy" {
+///         heavy_count += 1;
+///     }
+/// }
+///
+/// // "heavy" is expected to be sampled 9000 times
+/// assert!(heavy_count > 8700 && heavy_count < 9300);
+/// # }
+/// ```
+///
+/// Weights must be positive and finite:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::{weighted_sample, WeightError};
+/// # fn main() {
+/// let iter = vec![(1.0, 'a'), (-2.0, 'b'), (1.0, 'c')].into_iter();
+///
+/// assert_eq!(Err(WeightError::Negative(-2.0)), weighted_sample(&mut thread_rng(), 2, iter));
+/// # }
+/// ```
+pub fn weighted_sample<I, T, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Result<Vec<T>, WeightError>
+    where I : Iterator<Item=(f64, T)>,
+          RNG : Rng
+{
+    let mut samples = Vec::<T>::with_capacity(sample_size);
+    weighted_sample_into(&mut samples, rng, sample_size, iter)?;
+    Ok(samples)
+}
+
+/// Collect a weighted random sample of a known maximum size from an
+/// iterator of `(weight, item)` pairs into an existing Vec, using
+/// Algorithm A-Res.
+///
+/// If any weight is invalid, returns an error and leaves the Vec
+/// unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::weighted_sample_into;
+/// # fn main() {
+/// let iter = (0..10).map(|i| (1.0, i));
+/// let mut samples : Vec<i32> = vec![99,100];
+///
+/// weighted_sample_into(&mut samples, &mut thread_rng(), 4, iter).unwrap();
+///
+/// assert_eq!(6, samples.len());
+/// assert_eq!(99, samples[0]);
+/// assert_eq!(100, samples[1]);
+/// assert!(samples[2..].iter().all(|e| *e >= 0 && *e < 10));
+/// # }
+/// ```
+pub fn weighted_sample_into<I, T, RNG>(samples : &mut Vec<T>, rng : &mut RNG, sample_size : usize, iter : I) -> Result<(), WeightError>
+    where I : Iterator<Item=(f64, T)>,
+          RNG : Rng
+{
+    let mut heap = KeyHeap::new(sample_size);
+    
+    for (weight, item) in iter {
+        let weight = validate(weight)?;
+        heap.offer(random_key(rng, weight), item);
+    }
+    
+    samples.extend(heap.into_items());
+    Ok(())
+}
+
+/// Return a weighted random sample of a known maximum size from an
+/// iterator, calculating the weight of each item with the `weight`
+/// function, using Algorithm A-Res.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::weighted_sample_by;
+/// # fn main() {
+/// let request_sizes = vec![120, 4000, 35, 980, 12000];
+///
+/// let samples = weighted_sample_by(&mut thread_rng(), 2, request_sizes.into_iter(), |size| *size as f64).unwrap();
+///
+/// assert_eq!(2, samples.len());
+/// # }
+/// ```
+pub fn weighted_sample_by<I, F, RNG>(rng : &mut RNG, sample_size : usize, iter : I, mut weight : F) -> Result<Vec<I::Item>, WeightError>
+    where I : Iterator,
+          F : FnMut(&I::Item) -> f64,
+          RNG : Rng
+{
+    weighted_sample(rng, sample_size, iter.map(|item| (weight(&item), item)))
+}
+
+/// Return a random sample of a known maximum size from an iterator of
+/// `(weight, item)` pairs, where the probability of sampling an item
+/// is proportional to its weight.
+///
+/// Implements Efraimidis and Spirakis' Algorithm A-ExpJ, which samples
+/// with the same distribution as A-Res (see `weighted_sample`) but
+/// calculates how much weight to skip over between replacements,
+/// rather than drawing a random number for every item.  It makes
+/// O(k log(n/k)) random draws when sampling k items from n.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::weighted_sample_exp_jumps;
+/// # fn main() {
+/// let mut heavy_count = 0;
+///
+/// for _ in 0..10000 {
+///     let iter = (0..9).map(|_| (1.0, "light")).chain(Some((9.0, "heavy")));
+///     let samples = weighted_sample_exp_jumps(&mut thread_rng(), 1, iter).unwrap();
+///     if samples[0] == "heavy" {
+///         heavy_count += 1;
+///     }
+/// }
+///
+/// // "heavy" is expected to be sampled 5000 times
+/// assert!(heavy_count > 4700 && heavy_count < 5300);
+/// # }
+/// ```
+pub fn weighted_sample_exp_jumps<I, T, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Result<Vec<T>, WeightError>
+    where I : Iterator<Item=(f64, T)>,
+          RNG : Rng
+{
+    let mut samples = Vec::<T>::with_capacity(sample_size);
+    weighted_sample_exp_jumps_into(&mut samples, rng, sample_size, iter)?;
+    Ok(samples)
+}
+
+/// Collect a weighted random sample of a known maximum size from an
+/// iterator of `(weight, item)` pairs into an existing Vec, using
+/// Algorithm A-ExpJ.
+///
+/// If any weight is invalid, returns an error and leaves the Vec
+/// unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::{weighted_sample_exp_jumps_into, WeightError};
+/// # fn main() {
+/// let iter = vec![(1.0, 'a'), (0.0, 'b'), (1.0, 'c')].into_iter();
+/// let mut samples = vec!['z'];
+///
+/// assert_eq!(Err(WeightError::Zero), weighted_sample_exp_jumps_into(&mut samples, &mut thread_rng(), 2, iter));
+/// assert_eq!(vec!['z'], samples);
+/// # }
+/// ```
+pub fn weighted_sample_exp_jumps_into<I, T, RNG>(samples : &mut Vec<T>, rng : &mut RNG, sample_size : usize, mut iter : I) -> Result<(), WeightError>
+    where I : Iterator<Item=(f64, T)>,
+          RNG : Rng
+{
+    let mut heap = KeyHeap::new(sample_size);
+    
+    while !heap.is_full() {
+        match iter.next() {
+            Some((weight, item)) => {
+                let weight = validate(weight)?;
+                heap.offer(random_key(rng, weight), item);
+            }
+            None => break
+        }
+    }
+    
+    let mut jump = heap.min_key().map(|min_key| open01(rng).ln() / min_key);
+    
+    for (weight, item) in iter {
+        let weight = validate(weight)?;
+        
+        if let Some(ref mut remaining) = jump {
+            *remaining -= weight;
+            if *remaining <= 0.0 {
+                let min_key = heap.min_key().unwrap();
+                let threshold = (min_key * weight).exp();
+                let r = threshold + (1.0 - threshold) * open01(rng);
+                heap.offer(r.ln() / weight, item);
+                *remaining = open01(rng).ln() / heap.min_key().unwrap();
+            }
+        }
+    }
+    
+    samples.extend(heap.into_items());
+    Ok(())
+}
+
+/// Return a weighted random sample of a known maximum size from an
+/// iterator, calculating the weight of each item with the `weight`
+/// function, using Algorithm A-ExpJ.
+pub fn weighted_sample_exp_jumps_by<I, F, RNG>(rng : &mut RNG, sample_size : usize, iter : I, mut weight : F) -> Result<Vec<I::Item>, WeightError>
+    where I : Iterator,
+          F : FnMut(&I::Item) -> f64,
+          RNG : Rng
+{
+    weighted_sample_exp_jumps(rng, sample_size, iter.map(|item| (weight(&item), item)))
+}
+
+
+/// A weighted sample in which each item has an adjusted weight, such
+/// that the sum of the adjusted weights of the sampled items that
+/// satisfy any predicate is an unbiased estimate of the total weight
+/// of all the items that satisfy it.
+///
+/// Returned by `priority_sample`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrioritySample<T> {
+    threshold : f64,
+    samples : Vec<(f64, T)>
+}
+
+impl<T> PrioritySample<T> {
+    /// The sampled items, each paired with its adjusted weight.
+    pub fn samples(&self) -> &[(f64, T)] {
+        &self.samples
+    }
+    
+    /// The priority threshold, tau, below which items were not sampled.
+    /// It is zero if all the items were sampled.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+    
+    /// The number of items sampled.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    
+    /// Whether no items were sampled.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    
+    /// An unbiased estimate of the total weight of the items for which
+    /// `predicate` is true.
+    pub fn estimate<F>(&self, mut predicate : F) -> f64
+        where F : FnMut(&T) -> bool
+    {
+        self.samples.iter()
+            .filter(|(_, item)| predicate(item))
+            .map(|&(weight, _)| weight)
+            .sum()
+    }
+    
+    /// An unbiased estimate of the total weight of all the items.
+    pub fn total(&self) -> f64 {
+        self.estimate(|_| true)
+    }
+    
+    /// Consume the sample, returning the sampled items, each paired with
+    /// its adjusted weight.
+    pub fn into_samples(self) -> Vec<(f64, T)> {
+        self.samples
+    }
+}
+
+/// Return a priority sample of a known maximum size from an iterator
+/// of `(weight, item)` pairs, with which to estimate the total weight
+/// of arbitrary subsets of the items.
+///
+/// Implements Duffield, Lund and Thorup's priority sampling.  Each
+/// item is given the priority w/u, where w is its weight and u is
+/// uniformly random, and the `sample_size` items with the highest
+/// priorities are sampled.  The threshold tau is the next highest
+/// priority, and each sampled item's adjusted weight is the larger of
+/// its weight and tau.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::priority_sample;
+/// # fn main() {
+/// let flows = vec![(1500.0, 80), (40.0, 53), (900.0, 443), (60.0, 53), (7000.0, 443), (300.0, 22)];
+///
+/// let sample = priority_sample(&mut thread_rng(), 3, flows.into_iter()).unwrap();
+///
+/// assert_eq!(3, sample.len());
+///
+/// // Each sampled HTTPS flow counts for at least its own weight
+/// let https_bytes = sample.estimate(|port| *port == 443);
+/// assert!(https_bytes == 0.0 || https_bytes >= 900.0);
+/// # }
+/// ```
+///
+/// The estimates are unbiased:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::priority_sample;
+/// # fn main() {
+/// let trials = 10000;
+/// let mut even_total = 0.0;
+///
+/// for _ in 0..trials {
+///     let flows = (1..101).map(|n| (n as f64, n));
+///     let sample = priority_sample(&mut thread_rng(), 10, flows).unwrap();
+///     even_total += sample.estimate(|n| n % 2 == 0);
+/// }
+///
+/// // The total weight of the even items is 2550
+/// let mean = even_total / trials as f64;
+/// assert!(mean > 2450.0 && mean < 2650.0);
+/// # }
+/// ```
+pub fn priority_sample<I, T, RNG>(rng : &mut RNG, sample_size : usize, iter : I) -> Result<PrioritySample<T>, WeightError>
+    where I : Iterator<Item=(f64, T)>,
+          RNG : Rng
+{
+    let mut heap = KeyHeap::new(sample_size + 1);
+    
+    for (weight, item) in iter {
+        let weight = validate(weight)?;
+        heap.offer(weight / open01(rng), (weight, item));
+    }
+    
+    let threshold = if heap.len() > sample_size {
+        heap.pop_min().map_or(0.0, |(priority, _)| priority)
+    } else {
+        0.0
+    };
+    
+    Ok(PrioritySample {
+        threshold,
+        samples: heap.into_items().into_iter()
+            .map(|(weight, item)| (weight.max(threshold), item))
+            .collect()
+    })
+}
+
+// The log of the key u^(1/w) that A-Res gives to an item of weight w.
+// Comparing logs avoids the key underflowing to zero when the weight
+// is small.
+fn random_key<RNG : Rng>(rng : &mut RNG, weight : f64) -> f64 {
+    open01(rng).ln() / weight
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Keyed<T> {
+    pub(crate) key : f64,
+    pub(crate) item : T
+}
+
+// Keys are never NaN.  The ordering is reversed so that the top of a
+// BinaryHeap is the item with the smallest key.
+impl<T> Ord for Keyed<T> {
+    fn cmp(&self, other : &Self) -> Ordering {
+        other.key.partial_cmp(&self.key).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<T> PartialOrd for Keyed<T> {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Keyed<T> {
+    fn eq(&self, other : &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for Keyed<T> {}
+
+// Holds the items with the largest keys offered to it.
+#[derive(Clone, Debug)]
+pub(crate) struct KeyHeap<T> {
+    capacity : usize,
+    heap : BinaryHeap<Keyed<T>>
+}
+
+impl<T> KeyHeap<T> {
+    pub(crate) fn new(capacity : usize) -> Self {
+        KeyHeap {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity)
+        }
+    }
+    
+    pub(crate) fn is_full(&self) -> bool {
+        self.heap.len() >= self.capacity
+    }
+    
+    pub(crate) fn min_key(&self) -> Option<f64> {
+        self.heap.peek().map(|k| k.key)
+    }
+    
+    pub(crate) fn offer(&mut self, key : f64, item : T) {
+        if !self.is_full() {
+            self.heap.push(Keyed { key, item });
+        } else if let Some(mut min) = self.heap.peek_mut() {
+            if key > min.key {
+                *min = Keyed { key, item };
+            }
+        }
+    }
+    
+    pub(crate) fn len(&self) -> usize {
+        self.heap.len()
+    }
+    
+    pub(crate) fn pop_min(&mut self) -> Option<(f64, T)> {
+        self.heap.pop().map(|k| (k.key, k.item))
+    }
+    
+    pub(crate) fn clear(&mut self) {
+        self.heap.clear();
+    }
+    
+    pub(crate) fn items(&self) -> impl Iterator<Item=&T> {
+        self.heap.iter().map(|k| &k.item)
+    }
+    
+    pub(crate) fn into_items(self) -> Vec<T> {
+        self.heap.into_iter().map(|k| k.item).collect()
+    }
+    
+    // Multiply every key by a positive factor, which does not change
+    // the order of the keys, and update the items to match.
+    pub(crate) fn rescale<F>(&mut self, factor : f64, mut update : F)
+        where F : FnMut(&mut T)
+    {
+        let mut keyed = ::std::mem::take(&mut self.heap).into_vec();
+        for k in keyed.iter_mut() {
+            k.key *= factor;
+            update(&mut k.item);
+        }
+        self.heap = BinaryHeap::from(keyed);
+    }
+}
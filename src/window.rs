@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+use std::ops::Add;
+
+use rand::{Rng, ThreadRng};
+
+use super::sample;
+
+/// A reservoir that holds a uniform random sample of the most recent
+/// items pushed into it, within a window of a fixed number of items.
+///
+/// Implements Babcock, Datar and Motwani's priority sampling over a
+/// sliding window, extended to samples of more than one item.  Each
+/// item is given a random priority and the sample is the items in the
+/// window with the highest priorities.  The reservoir only retains
+/// items that have fewer than `sample_size` later items with a higher
+/// priority, so items drop out when they can no longer be sampled or
+/// when they leave the window.  The expected number of items retained
+/// is O(k log(n/k)), for a sample size of k and a window of n items.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::WindowReservoir;
+/// # fn main() {
+/// let mut reservoir = WindowReservoir::new(3, 10, thread_rng());
+///
+/// for i in 0..100 {
+///     reservoir.push(i);
+/// }
+///
+/// let samples = reservoir.samples();
+///
+/// assert_eq!(100, reservoir.seen());
+/// assert_eq!(3, samples.len());
+/// assert!(samples.iter().all(|e| **e >= 90 && **e < 100));
+/// # }
+/// ```
+///
+/// Every item in the window is equally likely to be sampled:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::WindowReservoir;
+/// # fn main() {
+/// let mut counts = [0usize; 10];
+///
+/// for _ in 0..10000 {
+///     let mut reservoir = WindowReservoir::new(3, 10, thread_rng());
+///     for i in 0..25 {
+///         reservoir.push(i);
+///     }
+///     for e in reservoir.samples() {
+///         counts[*e - 15] += 1;
+///     }
+/// }
+///
+/// // Each item is expected to be sampled 3000 times
+/// assert!(counts.iter().all(|&c| c > 2600 && c < 3400));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct WindowReservoir<T, RNG = ThreadRng> {
+    sample_size : usize,
+    window_size : usize,
+    entries : VecDeque<Entry<T>>,
+    seen : usize,
+    rng : RNG
+}
+
+#[derive(Clone, Debug)]
+struct Entry<T> {
+    position : usize,
+    priority : f64,
+    outranked_by : usize,
+    item : T
+}
+
+impl<T, RNG> WindowReservoir<T, RNG>
+    where RNG : Rng
+{
+    /// Create an empty reservoir that will hold a sample of at most
+    /// `sample_size` of the last `window_size` items pushed into it,
+    /// chosen with random numbers from `rng`.
+    pub fn new(sample_size : usize, window_size : usize, rng : RNG) -> Self {
+        WindowReservoir {
+            sample_size,
+            window_size,
+            entries: VecDeque::new(),
+            seen: 0,
+            rng
+        }
+    }
+    
+    /// Push an item into the window, expiring the oldest item if the
+    /// window is full.
+    pub fn push(&mut self, item : T) {
+        self.seen += 1;
+        
+        if self.sample_size > 0 {
+            let priority = self.rng.gen::<f64>();
+            let sample_size = self.sample_size;
+            
+            for entry in self.entries.iter_mut() {
+                if entry.priority < priority {
+                    entry.outranked_by += 1;
+                }
+            }
+            self.entries.retain(|e| e.outranked_by < sample_size);
+            
+            self.entries.push_back(Entry {
+                position: self.seen,
+                priority,
+                outranked_by: 0,
+                item
+            });
+        }
+        
+        while self.entries.front().is_some_and(|e| e.position + self.window_size <= self.seen) {
+            self.entries.pop_front();
+        }
+    }
+    
+    /// A uniform random sample of the items in the window, in the order
+    /// they were pushed.
+    pub fn samples(&self) -> Vec<&T> {
+        let mut ranked : Vec<usize> = (0..self.entries.len()).collect();
+        ranked.sort_by(|&a, &b| self.entries[b].priority.partial_cmp(&self.entries[a].priority).unwrap());
+        ranked.truncate(self.sample_size);
+        ranked.sort();
+        
+        ranked.into_iter().map(|i| &self.entries[i].item).collect()
+    }
+    
+    /// The number of items that have been pushed into the reservoir.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+    
+    /// The maximum number of items in the sample.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+    
+    /// The number of most recent items that the sample is drawn from.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+    
+    /// The number of items currently held by the reservoir, which is
+    /// at least as many as are in the sample.
+    pub fn retained(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl<T, RNG> Extend<T> for WindowReservoir<T, RNG>
+    where RNG : Rng
+{
+    fn extend<I : IntoIterator<Item=T>>(&mut self, iter : I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+
+/// A reservoir that holds the items pushed into it within a window of
+/// time, and draws uniform random samples from them.
+///
+/// Items are pushed with a timestamp, which can be of any ordered type
+/// to which the window's duration can be added, such as
+/// `std::time::Instant` and `std::time::Duration`, or integers.  An
+/// item is evicted once the latest time seen by the reservoir is at
+/// least the window's duration after the item's timestamp.  Items may
+/// arrive late, out of timestamp order: a late item is dropped if it is
+/// already outside the window, and otherwise kept in timestamp order
+/// with the others, to be evicted in its turn.
+///
+/// The sample is drawn from the items in the window with Algorithm R,
+/// as `sample` does.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::TimeWindowReservoir;
+/// # fn main() {
+/// let mut reservoir = TimeWindowReservoir::new(3, 300, thread_rng());
+///
+/// for t in 0..1000 {
+///     reservoir.push(t, format!("event at {}", t));
+/// }
+/// assert_eq!(300, reservoir.in_window());
+///
+/// reservoir.advance_to(1200);
+/// assert_eq!(99, reservoir.in_window());
+///
+/// let samples = reservoir.samples();
+/// assert_eq!(3, samples.len());
+/// # }
+/// ```
+///
+/// Using real time:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::TimeWindowReservoir;
+/// # use std::time::{Duration, Instant};
+/// # fn main() {
+/// let mut reservoir = TimeWindowReservoir::new(10, Duration::from_secs(5 * 60), thread_rng());
+///
+/// reservoir.push(Instant::now(), "disk full");
+///
+/// assert_eq!(vec![&"disk full"], reservoir.samples());
+/// # }
+/// ```
+///
+/// Late events are evicted by their own timestamps:
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate reservoir;
+/// # use rand::thread_rng;
+/// # use reservoir::TimeWindowReservoir;
+/// # fn main() {
+/// let mut reservoir = TimeWindowReservoir::new(3, 300, thread_rng());
+///
+/// reservoir.push(1000, "now");
+/// reservoir.push(10, "ancient");
+/// reservoir.push(900, "late");
+/// reservoir.push(1100, "later");
+///
+/// assert_eq!(3, reservoir.in_window());
+/// assert!(!reservoir.samples().contains(&&"ancient"));
+///
+/// reservoir.advance_to(1250);
+/// assert_eq!(vec![&"now", &"later"], reservoir.samples());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TimeWindowReservoir<Time, D, T, RNG = ThreadRng> {
+    sample_size : usize,
+    window : D,
+    items : VecDeque<(Time, T)>,
+    latest : Option<Time>,
+    rng : RNG
+}
+
+impl<Time, D, T, RNG> TimeWindowReservoir<Time, D, T, RNG>
+    where Time : Ord + Clone + Add<D, Output=Time>,
+          D : Clone,
+          RNG : Rng
+{
+    /// Create an empty reservoir that will draw samples of at most
+    /// `sample_size` items pushed within the last `window` of time,
+    /// chosen with random numbers from `rng`.
+    pub fn new(sample_size : usize, window : D, rng : RNG) -> Self {
+        TimeWindowReservoir {
+            sample_size,
+            window,
+            items: VecDeque::new(),
+            latest: None,
+            rng
+        }
+    }
+    
+    /// Push an item with the time at which it occurred, evicting any
+    /// items that are now outside the window.  An item that arrives
+    /// after the window has moved past its time is dropped.
+    pub fn push(&mut self, time : Time, item : T) {
+        self.advance_to(time.clone());
+        
+        if self.latest.as_ref().is_some_and(|latest| time.clone() + self.window.clone() <= *latest) {
+            return;
+        }
+        let index = self.items.partition_point(|(t, _)| *t <= time);
+        self.items.insert(index, (time, item));
+    }
+    
+    /// Evict the items that are outside the window ending at `now`.
+    pub fn advance_to(&mut self, now : Time) {
+        if self.latest.as_ref().is_some_and(|latest| *latest > now) {
+            return;
+        }
+        
+        while self.items.front().is_some_and(|(t, _)| t.clone() + self.window.clone() <= now) {
+            self.items.pop_front();
+        }
+        self.latest = Some(now);
+    }
+    
+    /// A uniform random sample of the items in the window.
+    pub fn samples(&mut self) -> Vec<&T> {
+        sample(&mut self.rng, self.sample_size, self.items.iter().map(|(_, item)| item))
+    }
+    
+    /// The number of items in the window.
+    pub fn in_window(&self) -> usize {
+        self.items.len()
+    }
+    
+    /// The maximum number of items in the sample.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+}